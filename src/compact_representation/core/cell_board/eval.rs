@@ -1,14 +1,13 @@
-use std::borrow::Borrow;
-
-use itertools::Itertools;
-
 use crate::{
     compact_representation::{core::dimensions::Dimensions, CellNum},
-    types::{self, HeadGettableGame, Move, SnakeId, N_MOVES},
+    types::{EliminationCause, HeadGettableGame, Move, SnakeId, SquadQueryableGame, N_MOVES},
+    wire_representation::SquadSettings,
 };
 
 use super::{CellBoard, CellIndex};
 
+const MAX_HEALTH: u8 = 100;
+
 /// Which mode to evaluate in
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EvaluateMode {
@@ -46,9 +45,10 @@ pub struct AliveMoveResult<T: CellNum> {
 pub enum SinglePlayerMoveResult<T: CellNum> {
     /// Represents the given snake is alive after phase 1 of evaluation
     Alive(AliveMoveResult<T>),
-    /// Represents the snake died during phase 1. Cause it ran into a snake (including itself)
-    /// [excluding head to heads] or went out of bounds
-    Dead,
+    /// Represents the snake died during phase 1. Cause it ran into its own neck, went out of
+    /// bounds or ran out of health. The cause is `None` for moves that weren't simulated, such as
+    /// the moves of snakes that were already dead
+    Dead(Option<EliminationCause<SnakeId>>),
 }
 
 impl<T: CellNum> SinglePlayerMoveResult<T> {
@@ -60,103 +60,122 @@ impl<T: CellNum> SinglePlayerMoveResult<T> {
     }
 
     pub fn is_dead(&self) -> bool {
-        matches!(self, SinglePlayerMoveResult::Dead)
+        matches!(self, SinglePlayerMoveResult::Dead(_))
     }
 }
 
 impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
-    pub fn generate_state<'a, S: 'a>(
+    pub fn generate_state<'a>(
         &self,
-        moves: impl Iterator<Item = &'a (SnakeId, S)>,
+        moves: impl Iterator<Item = (SnakeId, &'a [Move])>,
         mode: EvaluateMode,
-    ) -> [[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES]
-    where
-        S: Borrow<[Move]>,
-    {
-        let mut new_heads = [[SinglePlayerMoveResult::Dead; 4]; MAX_SNAKES];
+    ) -> [[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES] {
+        let mut new_heads = [[SinglePlayerMoveResult::Dead(None); 4]; MAX_SNAKES];
 
         for (id, mvs) in moves {
             if self.healths[id.as_usize()] == 0 {
                 continue;
             }
-            for m in mvs.borrow() {
-                let old_head = self.get_head_as_native_position(id);
-                let old_tail = self
-                    .get_cell(old_head)
-                    .get_tail_position(old_head)
-                    .unwrap_or_else(|| {
-                        panic!(
-                            "We came from a head so we should have a tail snake: {} health: {}",
-                            id.0,
-                            self.healths[id.as_usize()]
-                        )
-                    });
+            let old_head = self.get_head_as_native_position(&id);
+            let old_tail = self
+                .get_snake_cell(old_head, id)
+                .get_tail_position(old_head)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "We came from a head so we should have a tail snake: {} health: {}",
+                        id.0,
+                        self.healths[id.as_usize()]
+                    )
+                });
+
+            // We calculate the 'neck' so that we can avoid the 'instant death'
+            // of moving into your neck
+            let neck = {
+                let mut curr = old_tail;
+                let mut prev = curr;
+
+                while curr != old_head {
+                    prev = curr;
+                    curr = self
+                        .get_snake_cell(curr, id)
+                        .get_next_index()
+                        .unwrap_or_else(|| {
+                            eprintln!("{}", self);
+                            panic!("snake is inconsistent")
+                        });
+                }
+
+                prev
+            };
 
-                let new_head_position =
-                    old_head.into_position(Self::width()).add_vec(m.to_vector());
+            let old_tail_cell = self.get_snake_cell(old_tail, id);
+            let new_tail = if old_tail_cell.is_stacked() || self.is_growing(id) {
+                old_tail
+            } else {
+                old_tail_cell
+                    .get_next_index()
+                    .expect("We specifically went to a tail so this shouldn't fail")
+            };
+
+            for m in mvs {
+                let new_head_position = old_head
+                    .into_position(self.get_actual_width())
+                    .add_vec(m.to_vector());
                 let new_head = match mode {
                     EvaluateMode::Wrapped => self.as_wrapped_cell_index(new_head_position),
                     EvaluateMode::Standard => {
                         if self.off_board(new_head_position) {
+                            new_heads[id.as_usize()][m.as_index()] =
+                                SinglePlayerMoveResult::Dead(Some(EliminationCause::OutOfBounds));
                             continue;
                         } else {
-                            CellIndex::<T>::new(new_head_position, Self::width())
+                            CellIndex::<T>::new(new_head_position, self.get_actual_width())
                         }
                     }
                 };
 
-                // TWe calculate the 'neck' so that we can avoid the 'instant death'
-                // of moving into your neck
-                let neck = {
-                    let mut curr = old_tail;
-                    let mut prev = curr;
-
-                    while curr != old_head {
-                        prev = curr;
-                        curr = self.get_cell(curr).get_next_index().unwrap_or_else(|| {
-                            eprintln!("{}", self);
-                            panic!("snake is inconsistent")
-                        });
-                    }
-
-                    prev
-                };
-                if new_head == neck {
-                    continue;
-                }
-
-                let old_tail_cell = self.get_cell(old_tail);
-                let new_tail = if old_tail_cell.is_stacked() {
-                    old_tail
-                } else {
-                    old_tail_cell
-                        .get_next_index()
-                        .expect("We specifically went to a tail so this shouldn't fail")
-                };
-
-                let mut new_health = self.healths[id.as_usize()];
-                new_health = new_health.saturating_sub(1);
-                if self.get_cell(new_head).is_hazard() {
-                    new_health = new_health.saturating_sub(self.hazard_damage);
+                // health math is done in i32 as hazard damage can be negative, which heals
+                let mut new_health = self.healths[id.as_usize()] as i32 - 1;
+                let starved = new_health <= 0;
+                let hazard_count = self.get_cell(new_head).get_hazard_count();
+                if hazard_count > 0 {
+                    // stacked hazards each apply their damage
+                    new_health -= self.hazard_damage as i32 * hazard_count as i32;
                 }
+                let mut new_health = new_health.clamp(0, MAX_HEALTH as i32) as u8;
 
                 let ate_food = self.get_cell(new_head).is_food();
                 let mut new_length = self.lengths[id.as_usize()];
 
                 if ate_food {
-                    new_health = 100;
+                    new_health = MAX_HEALTH;
                     new_length = new_length.saturating_add(1);
                 };
 
                 if new_health == 0 {
+                    let cause = if starved {
+                        EliminationCause::Starvation
+                    } else {
+                        EliminationCause::HazardDamage
+                    };
+                    new_heads[id.as_usize()][m.as_index()] =
+                        SinglePlayerMoveResult::Dead(Some(cause));
                     continue;
                 };
 
+                // This happens after the health checks, as snakes that run out of health are
+                // eliminated before any collisions are considered
+                if new_head == neck {
+                    new_heads[id.as_usize()][m.as_index()] =
+                        SinglePlayerMoveResult::Dead(Some(EliminationCause::SelfCollision));
+                    continue;
+                }
+
                 new_heads[id.as_usize()][m.as_index()] =
                     SinglePlayerMoveResult::Alive(AliveMoveResult {
-                        id: *id,
+                        id,
                         new_head,
                         old_head,
                         new_tail,
@@ -171,16 +190,26 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         new_heads
     }
 
-    pub fn evaluate_moves_with_state<'a>(
+    pub fn evaluate_moves_with_state(
+        &self,
+        moves: &[(SnakeId, Move)],
+        new_heads: &[[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES],
+    ) -> Self {
+        self.evaluate_moves_with_state_and_eliminations(moves, new_heads)
+            .0
+    }
+
+    /// Same as [Self::evaluate_moves_with_state], but also returns why each snake that died
+    /// during this evaluation was eliminated
+    #[allow(clippy::type_complexity)]
+    pub fn evaluate_moves_with_state_and_eliminations(
         &self,
-        moves: impl Iterator<Item = &'a (SnakeId, crate::types::Move)>,
+        moves: &[(SnakeId, Move)],
         new_heads: &[[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES],
-    ) -> Self
-    where
-        <Self as types::SnakeIDGettableGame>::SnakeIDType: 'a,
-    {
-        let moves = moves.collect_vec();
+    ) -> (Self, [Option<EliminationCause<SnakeId>>; MAX_SNAKES]) {
         let mut new = *self;
+        let mut eliminations = [None; MAX_SNAKES];
+        let mut self_collided = [false; MAX_SNAKES];
 
         for (id, m) in moves.iter() {
             let result = new_heads[id.as_usize()][m.as_index()];
@@ -201,11 +230,15 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                     // impossible to correctly remove the tail if the snake dies.
 
                     // Remove old tail
-                    let old_tail_cell = new.get_cell(old_tail);
+                    let old_tail_cell = new.get_snake_cell(old_tail, id);
                     if old_tail_cell.is_double_stacked_piece() {
                         new.set_cell_body_piece(old_tail, id, old_tail_cell.get_idx());
+                    } else if new_tail == old_tail {
+                        // the snake is still growing, or hasn't left its starting cell yet, so
+                        // its tail stays where it is. A snake that hasn't moved is laid out when
+                        // its head moves, as its starting cell is also its head until then
                     } else {
-                        new.cell_remove(old_tail);
+                        new.remove_snake_piece(old_tail, id);
                         new.set_cell_head(old_head, id, new_tail)
                     }
 
@@ -215,15 +248,41 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
 
                     // Step 2: Any Battlesnake that has found food will consume it
                     // Reset health to max if ate food
-                    if ate_food {
-                        let new_tail_cell = new.get_cell(new_tail);
+                    // a snake that hasn't moved yet can't stack its tail any further, its length
+                    // keeps track of the new segment instead
+                    if ate_food && !old_tail_cell.is_triple_stacked_piece() {
+                        let new_tail_cell = new.get_snake_cell(new_tail, id);
                         new.set_cell_double_stacked(new_tail, id, new_tail_cell.get_idx());
 
                         // Food is removed naturally by overriding the Cell with the body, which will
                         // happen later
                     }
                 }
-                SinglePlayerMoveResult::Dead => new.kill_and_remove(*id),
+                SinglePlayerMoveResult::Dead(Some(EliminationCause::SelfCollision)) => {
+                    // a snake that moves back on to its own neck is eliminated, but like in the
+                    // engine the rest of its body still moves and blocks other snakes this turn,
+                    // so it's only removed once collisions have been resolved
+                    eliminations[id.as_usize()] = Some(EliminationCause::SelfCollision);
+                    self_collided[id.as_usize()] = true;
+                    let head = new.heads[id.as_usize()];
+                    let old_tail = new
+                        .get_snake_cell(head, *id)
+                        .get_tail_position(head)
+                        .expect("We came from a head so we should have a tail");
+                    let old_tail_cell = new.get_snake_cell(old_tail, *id);
+                    if old_tail_cell.is_double_stacked_piece() {
+                        new.set_cell_body_piece(old_tail, *id, old_tail_cell.get_idx());
+                    } else if new.is_growing(*id) {
+                        // the tail stays where it is
+                    } else if let Some(new_tail) = old_tail_cell.get_next_index() {
+                        new.remove_snake_piece(old_tail, *id);
+                        new.set_cell_head(head, *id, new_tail);
+                    }
+                }
+                SinglePlayerMoveResult::Dead(cause) => {
+                    eliminations[id.as_usize()] = cause;
+                    new.kill_and_remove(*id)
+                }
             }
         }
 
@@ -233,37 +292,77 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         let mut to_kill = [false; MAX_SNAKES];
 
         // Step 4c-d: Collision besides head to head
+        let allow_body_collisions = self
+            .squad_settings
+            .map_or(false, |s| s.allow_body_collisions);
         for (id, m) in moves.iter() {
             let result = new_heads[id.as_usize()][m.as_index()];
 
             if let SinglePlayerMoveResult::Alive(AliveMoveResult { id, new_head, .. }) = result {
                 let new_head_cell = new.get_cell(new_head);
+                let occupants = [new_head_cell, new.under[new_head.0.as_usize()]];
+                let mut occupants = occupants.iter().filter_map(|c| c.get_snake_id());
 
-                if new_head_cell.is_body_segment() || new_head_cell.is_head() {
+                // the engine resurrects snakes that only ran in to a teammate's body
+                let passes_through = allow_body_collisions
+                    && occupants
+                        .clone()
+                        .all(|other| other != id && self.is_teammate(&id, &other));
+
+                if (new_head_cell.is_body_segment() || new_head_cell.is_head()) && !passes_through {
                     to_kill[id.as_usize()] = true;
+                    // running in to your own body takes precedence, like in the engine
+                    eliminations[id.as_usize()] = if occupants.any(|other| other == id) {
+                        Some(EliminationCause::SelfCollision)
+                    } else {
+                        new_head_cell
+                            .get_snake_id()
+                            .map(EliminationCause::BodyCollision)
+                    };
                 }
             }
         }
 
         // Step 4e: Head to Head collisions
-        let grouped_heads = moves
-            .iter()
-            .map(|(id, m)| new_heads[id.as_usize()][m.as_index()])
-            .filter_map(|result| result.to_alive_struct())
-            .into_group_map_by(|t| t.new_head);
-        let head_to_head_collistions = grouped_heads
-            .iter()
-            .filter(|(_key, values)| values.len() >= 2);
+        let alive_results = {
+            let mut alive_results = [None; MAX_SNAKES];
+            for (result, (id, m)) in alive_results.iter_mut().zip(moves.iter()) {
+                *result = new_heads[id.as_usize()][m.as_index()].to_alive_struct();
+            }
+            alive_results
+        };
+        let mut grouped = [false; MAX_SNAKES];
+
+        for i in 0..moves.len() {
+            let head_to_head_collision_pos = match alive_results[i] {
+                Some(result) if !grouped[i] => result.new_head,
+                _ => continue,
+            };
+
+            // group every snake moving on to the same cell, without allocating
+            let mut group = [None; MAX_SNAKES];
+            let mut group_len = 0;
+            for j in i..moves.len() {
+                if let Some(other) = alive_results[j] {
+                    if other.new_head == head_to_head_collision_pos {
+                        group[group_len] = Some(other);
+                        group_len += 1;
+                        grouped[j] = true;
+                    }
+                }
+            }
+            if group_len < 2 {
+                continue;
+            }
+            let snake_move_info = group[..group_len].iter().flatten();
 
-        for (head_to_head_collision_pos, snake_move_info) in head_to_head_collistions {
             let max_length = snake_move_info
-                .iter()
+                .clone()
                 .map(|i| (*i, new.get_length(i.id)))
                 .max_by_key(|x| x.1)
                 .unwrap()
                 .1;
-            let snake_ids = snake_move_info.iter().map(|i| i.id).collect_vec();
-            let cell = new.get_cell(*head_to_head_collision_pos);
+            let cell = new.get_cell(head_to_head_collision_pos);
             // consider this board:
             //   s . . f . . s s s 3 s
             //   s s s . . . . s s . .
@@ -282,23 +381,25 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
             // and snake 0 has a body segment (currently it's neck) on 0,
             // this will cause a head to head collision between snake 1 and snake 3 on snake 0's neck.
             // this statement needs to be added to the winner check, because if it isn't, the neck cell for
-            // snake 0 will be removed, causing the body to go in to an inconsistent state
-            let head_to_head_collision_on_another_snake = cell.is_body_segment()
-                && !cell.is_head()
-                && !snake_ids.contains(&cell.get_snake_id().unwrap());
+            // snake 0 will be removed, causing the body to go in to an inconsistent state.
+            // The same goes for the old head of a snake moving away, which becomes its neck, and
+            // for the body of a snake in the collision, which has to be walked when it is removed
+            let head_to_head_collision_on_a_snake = cell.get_snake_id().is_some();
 
             let multiple_snakes_max_length = snake_move_info
-                .iter()
+                .clone()
                 .filter(|x| new.get_length(x.id) == max_length)
                 .count()
                 != 1;
 
-            let winner = if multiple_snakes_max_length || head_to_head_collision_on_another_snake {
+            // a winner on someone else's body was already killed by the body collision, unless it
+            // passed through a teammate
+            let winner = if multiple_snakes_max_length {
                 None
             } else {
                 Some(
                     snake_move_info
-                        .iter()
+                        .clone()
                         .map(|i| (*i, new.get_length(i.id)))
                         .max_by_key(|x| x.1)
                         .unwrap()
@@ -307,17 +408,33 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
             };
 
             for AliveMoveResult { id: dead, .. } in snake_move_info
-                .iter()
+                .clone()
                 .filter(|x| Some(x.id) != winner.map(|x| x.id))
             {
                 to_kill[dead.as_usize()] = true;
+                // body collisions take precedence, like in the official engine. Otherwise blame
+                // the longest other snake in the collision
+                if eliminations[dead.as_usize()].is_none() {
+                    let killer = snake_move_info
+                        .clone()
+                        .filter(|x| x.id != *dead)
+                        .max_by_key(|x| new.get_length(x.id))
+                        .unwrap()
+                        .id;
+                    eliminations[dead.as_usize()] =
+                        Some(EliminationCause::HeadToHeadCollision(killer));
+                }
             }
 
-            if winner.is_none() && !head_to_head_collision_on_another_snake {
-                new.cell_remove(*head_to_head_collision_pos);
+            if winner.is_none() && !head_to_head_collision_on_a_snake {
+                new.cell_remove(head_to_head_collision_pos);
             }
         }
 
+        for (id, _) in moves.iter().filter(|(id, _)| self_collided[id.as_usize()]) {
+            new.kill_and_remove(*id);
+        }
+
         for result in moves
             .iter()
             .map(|(id, m)| new_heads[id.as_usize()][m.as_index()])
@@ -338,7 +455,7 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
                     new.heads[id.as_usize()] = new_head;
                     new.set_cell_head(new_head, id, new_tail);
 
-                    let old_head_cell = self.get_cell(old_head);
+                    let old_head_cell = self.get_snake_cell(old_head, id);
                     if old_head_cell.is_triple_stacked_piece() {
                         new.set_cell_double_stacked(old_head, id, new_head);
                     } else {
@@ -348,6 +465,99 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
             }
         }
 
-        new
+        if let Some(squad_settings) = self.squad_settings {
+            new.apply_squad_rules(self, squad_settings, &mut eliminations);
+        }
+
+        for i in 0..MAX_SNAKES {
+            new.eliminated_last_turn[i] = self.healths[i] != 0 && new.healths[i] == 0;
+        }
+
+        (new, eliminations)
+    }
+
+    /// how many segments the cells of a snake hold. A tail is only ever stacked once, so the
+    /// cells can hold fewer segments than the snake's length while it grows by more than that
+    /// at once. The remaining segments are stacked on the tail
+    pub(crate) fn represented_length(&self, sid: SnakeId) -> u16 {
+        let head = self.heads[sid.as_usize()];
+        let tail = self
+            .get_snake_cell(head, sid)
+            .get_tail_position(head)
+            .expect("We came from a head so we should have a tail");
+        let tail_cell = self.get_snake_cell(tail, sid);
+        let mut length = if tail_cell.is_triple_stacked_piece() {
+            3
+        } else if tail_cell.is_double_stacked_piece() {
+            2
+        } else {
+            1
+        };
+        let mut curr = tail;
+        while curr != head {
+            curr = self
+                .get_snake_cell(curr, sid)
+                .get_next_index()
+                .expect("snake is inconsistent");
+            length += 1;
+        }
+        length
+    }
+
+    /// does the snake have segments stacked on its tail beyond what the cells hold? Its tail
+    /// stays put until they have all been laid down
+    fn is_growing(&self, sid: SnakeId) -> bool {
+        self.represented_length(sid) < self.lengths[sid.as_usize()]
+    }
+
+    /// Applies the squad rules after everyone has moved, in the same order as the engine
+    fn apply_squad_rules(
+        &mut self,
+        before: &Self,
+        squad_settings: SquadSettings,
+        eliminations: &mut [Option<EliminationCause<SnakeId>>; MAX_SNAKES],
+    ) {
+        if squad_settings.shared_elimination {
+            for (i, elimination) in eliminations.iter_mut().enumerate() {
+                let sid = SnakeId(i as u8);
+                if self.healths[i] == 0 {
+                    continue;
+                }
+                let eliminated_teammate = (0..MAX_SNAKES).map(|j| SnakeId(j as u8)).find(|j| {
+                    self.is_teammate(&sid, j)
+                        && before.healths[j.as_usize()] > 0
+                        && self.healths[j.as_usize()] == 0
+                });
+                if let Some(teammate) = eliminated_teammate {
+                    self.kill_and_remove(sid);
+                    *elimination = Some(EliminationCause::SquadEliminated(teammate));
+                }
+            }
+        }
+
+        for i in 0..MAX_SNAKES {
+            let sid = SnakeId(i as u8);
+            if self.healths[i] == 0 {
+                continue;
+            }
+            let teammates = (0..MAX_SNAKES)
+                .map(|j| SnakeId(j as u8))
+                .filter(|j| self.healths[j.as_usize()] > 0 && self.is_teammate(&sid, j));
+            let health = teammates.clone().map(|j| self.healths[j.as_usize()]).max();
+            let length = teammates.map(|j| self.lengths[j.as_usize()]).max();
+
+            if squad_settings.shared_health {
+                if let Some(health) = health {
+                    self.healths[i] = self.healths[i].max(health);
+                }
+            }
+
+            if squad_settings.shared_length {
+                // the new segments go on the tail, which stays put until they are laid down
+                if let Some(length) = length {
+                    self.lengths[i] = self.lengths[i].max(length);
+                }
+            }
+        }
     }
 }
@@ -12,7 +12,11 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         self.cell_is_hazard(*pos)
     }
 
-    fn get_hazard_damage(&self) -> u8 {
-        self.hazard_damage
+    fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+        self.get_cell(*pos).get_hazard_count()
+    }
+
+    fn get_hazard_damage(&self) -> i32 {
+        self.hazard_damage as i32
     }
 }
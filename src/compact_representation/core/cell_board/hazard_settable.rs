@@ -12,7 +12,15 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         self.cells[pos.0.as_usize()].set_hazard();
     }
 
+    fn add_hazard(&mut self, pos: Self::NativePositionType) {
+        self.cells[pos.0.as_usize()].add_hazard();
+    }
+
     fn clear_hazard(&mut self, pos: Self::NativePositionType) {
         self.cells[pos.0.as_usize()].clear_hazard();
     }
+
+    fn remove_hazard(&mut self, pos: Self::NativePositionType) {
+        self.cells[pos.0.as_usize()].remove_hazard();
+    }
 }
@@ -6,7 +6,7 @@ use crate::{
         CellNum,
     },
     types::LengthGettableGame,
-    wire_representation::Position,
+    wire_representation::ascii::{self, AsciiCell},
 };
 
 use super::CellBoard;
@@ -29,32 +29,55 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let width = self.get_actual_width();
-        let height = self.get_actual_height();
-        writeln!(f)?;
-        for y in 0..height {
-            for x in 0..width {
-                let y = height - y - 1;
-                let position = Position {
-                    x: x as i32,
-                    y: y as i32,
-                };
-                let cell_idx = CellIndex::new(position, width);
-                if self.cell_is_snake_head(cell_idx) {
-                    let id = self.get_snake_id_at(cell_idx);
-                    write!(f, "{}", id.unwrap().as_usize())?;
-                } else if self.cell_is_food(cell_idx) {
-                    write!(f, "f")?
-                } else if self.cell_is_body(cell_idx) {
-                    write!(f, "s")?
-                } else if self.cell_is_hazard(cell_idx) {
-                    write!(f, "x")?
+        let labels = (0..MAX_SNAKES)
+            .map(|idx| {
+                std::char::from_digit(idx as u32, 10).unwrap_or_else(|| ascii::snake_label(idx))
+            })
+            .collect::<Vec<_>>();
+        ascii::write_grid(
+            f,
+            width as u32,
+            self.get_actual_height() as u32,
+            &labels,
+            |position| {
+                let cell_idx = CellIndex::<T>::new(position, width);
+                let under = self.under[cell_idx.0.as_usize()];
+                // a head passing through a teammate is drawn over the teammate's body
+                let cell = if under.is_head() {
+                    under
                 } else {
-                    debug_assert!(self.cells[cell_idx.0.as_usize()].is_empty());
-                    write!(f, ".")?
+                    self.get_cell(cell_idx)
+                };
+                match cell.get_snake_id().map(|id| id.as_usize()) {
+                    Some(id) if cell.is_head() => AsciiCell::Head(id),
+                    Some(id) if cell.is_double_stacked_piece() => AsciiCell::Stacked(id),
+                    Some(id) if cell.is_body() => {
+                        let next = cell.get_next_index().unwrap().into_position(width);
+                        match ascii::direction(position, next) {
+                            Some(mv) => AsciiCell::Body(id, mv),
+                            None => AsciiCell::Segment(id),
+                        }
+                    }
+                    _ if cell.is_food() => AsciiCell::Food,
+                    _ if cell.is_hazard() => AsciiCell::Hazard,
+                    _ => {
+                        debug_assert!(cell.is_empty());
+                        AsciiCell::Empty
+                    }
                 }
-                write!(f, " ")?;
+            },
+        )?;
+        for (idx, health) in self.healths.iter().enumerate() {
+            if *health > 0 {
+                ascii::write_legend(
+                    f,
+                    &labels,
+                    idx,
+                    *health as i64,
+                    self.lengths[idx] as usize,
+                    None,
+                )?;
             }
-            writeln!(f)?;
         }
         let hash_repr = self.pack_as_hash();
         writeln!(f, "{}", serde_json::to_string(&hash_repr).unwrap())?;
@@ -6,8 +6,11 @@ use itertools::Itertools;
 
 use crate::types::SnakeIDMap;
 use crate::types::SnakeId;
+use crate::types::SquadQueryableGame;
+use crate::wire_representation::BattleSnake;
 use crate::wire_representation::Game;
 use crate::wire_representation::Position;
+use crate::wire_representation::SquadSettings;
 
 use super::dimensions::Dimensions;
 use super::Cell;
@@ -27,26 +30,63 @@ mod position_gettable;
 mod size_determinable;
 mod snake_body_gettable;
 mod snake_id_gettable;
+mod squad_queryable;
 mod victor_determinable;
 mod you_determinable;
 
-pub use eval::EvaluateMode;
+pub use eval::{EvaluateMode, SinglePlayerMoveResult};
 
 /// A compact board representation that is significantly faster for simulation than
 /// `battlesnake_game_types::wire_representation::Game`.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone)]
 pub struct CellBoard<
     T: CN,
     DimensionsType: Dimensions,
     const BOARD_SIZE: usize,
     const MAX_SNAKES: usize,
 > {
-    hazard_damage: u8,
+    /// damage done by each hazard a snake moves on to. Negative values heal
+    hazard_damage: i8,
     cells: [Cell<T>; BOARD_SIZE],
+    /// the segments of snakes that moved through a teammate's body, which share a cell with the
+    /// teammate's segment in `cells`. Always empty unless squads allow body collisions. Hazards
+    /// and food are only ever kept in `cells`
+    under: [Cell<T>; BOARD_SIZE],
     healths: [u8; MAX_SNAKES],
     heads: [CellIndex<T>; MAX_SNAKES],
     lengths: [u16; MAX_SNAKES],
     dimensions: DimensionsType,
+    /// true when playing the solo ruleset, where the game only ends once every snake is dead
+    solo: bool,
+    /// the squad each snake is on, 0 for snakes that aren't on a squad
+    squads: [u8; MAX_SNAKES],
+    /// the squad rules in effect, `None` unless playing the squad ruleset
+    squad_settings: Option<SquadSettings>,
+    /// which snakes were eliminated by the move that produced this board. This is history rather
+    /// than state, so it is left out when comparing boards
+    eliminated_last_turn: [bool; MAX_SNAKES],
+}
+
+impl<T: CN, D: Dimensions + PartialEq, const BOARD_SIZE: usize, const MAX_SNAKES: usize> PartialEq
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.hazard_damage == other.hazard_damage
+            && self.cells == other.cells
+            && self.under == other.under
+            && self.healths == other.healths
+            && self.heads == other.heads
+            && self.lengths == other.lengths
+            && self.dimensions == other.dimensions
+            && self.solo == other.solo
+            && self.squads == other.squads
+            && self.squad_settings == other.squad_settings
+    }
+}
+
+impl<T: CN, D: Dimensions + Eq, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Eq
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
 }
 
 #[allow(dead_code)]
@@ -75,14 +115,16 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             let health = self.healths[i];
             if health > 0 {
                 let head_index = self.heads[i];
-                let tail_index = self.get_cell(head_index).get_tail_position(head_index);
+                let tail_index = self
+                    .get_snake_cell(head_index, snake_id)
+                    .get_tail_position(head_index);
                 if tail_index.is_none() {
                     return false;
                 }
                 let tail_index = tail_index.unwrap();
                 let mut index = tail_index;
                 while index != head_index {
-                    let cell = self.get_cell(index);
+                    let cell = self.get_snake_cell(index, snake_id);
                     if !cell.is_body_segment() {
                         return false;
                     }
@@ -104,7 +146,37 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     /// with const generics is hard
     pub fn pack_as_hash(&self) -> HashMap<String, Vec<u32>> {
         let mut hash = HashMap::new();
-        hash.insert("hazard_damage".to_string(), vec![self.hazard_damage as u32]);
+        hash.insert(
+            "hazard_damage".to_string(),
+            vec![self.hazard_damage as u8 as u32],
+        );
+        hash.insert("solo".to_string(), vec![self.solo as u32]);
+        hash.insert(
+            "eliminated_last_turn".to_string(),
+            self.eliminated_last_turn
+                .iter()
+                .map(|x| *x as u32)
+                .collect(),
+        );
+        hash.insert(
+            "squads".to_string(),
+            self.squads.iter().map(|x| *x as u32).collect(),
+        );
+        hash.insert(
+            "squad_settings".to_string(),
+            self.squad_settings
+                .iter()
+                .flat_map(|s| {
+                    [
+                        s.allow_body_collisions,
+                        s.shared_elimination,
+                        s.shared_health,
+                        s.shared_length,
+                    ]
+                })
+                .map(|x| x as u32)
+                .collect(),
+        );
         hash.insert(
             "actual_width".to_string(),
             vec![self.get_actual_width() as u32],
@@ -125,17 +197,102 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             "cells".to_string(),
             self.cells.iter().map(|x| x.pack_as_u32()).collect(),
         );
+        // left out when no snakes share cells, which keeps older packed boards comparable
+        if self.under.iter().any(|x| !x.is_empty()) {
+            hash.insert(
+                "under".to_string(),
+                self.under.iter().map(|x| x.pack_as_u32()).collect(),
+            );
+        }
         hash
     }
 
+    /// checks that a packed hash repr can be unpacked in to a CellBoard of this size,
+    /// `from_packed_hash` panics on hashes that fail this check
+    pub fn validate_packed_hash(hash: &HashMap<String, Vec<u32>>) -> Result<(), Box<dyn Error>> {
+        for key in ["hazard_damage", "actual_width"] {
+            if hash.get(key).map(|v| v.is_empty()).unwrap_or(true) {
+                return Err(format!("packed board is missing {}", key).into());
+            }
+        }
+        for key in ["healths", "lengths", "heads"] {
+            match hash.get(key) {
+                None => return Err(format!("packed board is missing {}", key).into()),
+                Some(v) if v.len() > MAX_SNAKES => {
+                    return Err(format!("packed board has too many {}", key).into())
+                }
+                Some(_) => {}
+            }
+        }
+        match hash.get("cells") {
+            Some(cells) if cells.len() == BOARD_SIZE => {}
+            _ => return Err(format!("packed board must have {} cells", BOARD_SIZE).into()),
+        }
+        match hash.get("under") {
+            Some(under) if under.len() != BOARD_SIZE => {
+                return Err(format!("packed board must have {} cells under", BOARD_SIZE).into())
+            }
+            _ => {}
+        }
+        if hash["heads"]
+            .iter()
+            .any(|head| *head as usize >= BOARD_SIZE)
+        {
+            return Err("packed board has a head off the board".into());
+        }
+        for packed in hash["cells"]
+            .iter()
+            .chain(hash.get("under").into_iter().flatten())
+        {
+            // the index is read before it is narrowed down to T, which could wrap it around
+            if (packed >> 16) as usize >= BOARD_SIZE {
+                return Err("packed board has a cell pointing off the board".into());
+            }
+            let cell = Cell::<T>::from_u32(*packed);
+            if cell
+                .get_snake_id()
+                .map_or(false, |id| id.as_usize() >= MAX_SNAKES)
+            {
+                return Err("packed board has a cell with an unknown snake".into());
+            }
+        }
+        Ok(())
+    }
+
     /// unpacks a packed hash repr back in to a CellBoard
     pub fn from_packed_hash(hash: &HashMap<String, Vec<u32>>) -> Self {
-        let hazard_damage = hash.get("hazard_damage").unwrap()[0] as u8;
+        let hazard_damage = hash.get("hazard_damage").unwrap()[0] as u8 as i8;
         let actual_width = hash.get("actual_width").unwrap()[0] as u8;
         let actual_height = hash
             .get("actual_height")
             .map(|h| h[0] as u8)
             .unwrap_or(actual_width);
+        let solo = hash.get("solo").map(|s| s[0] != 0).unwrap_or(false);
+
+        let mut eliminated_last_turn = [false; MAX_SNAKES];
+        let eliminated_iter = hash
+            .get("eliminated_last_turn")
+            .into_iter()
+            .flatten()
+            .map(|x| *x != 0);
+        for (idx, eliminated) in eliminated_iter.enumerate() {
+            eliminated_last_turn[idx] = eliminated;
+        }
+
+        let mut squads = [0; MAX_SNAKES];
+        let squads_iter = hash.get("squads").into_iter().flatten().map(|x| *x as u8);
+        for (idx, squad) in squads_iter.enumerate() {
+            squads[idx] = squad;
+        }
+        let squad_settings = hash
+            .get("squad_settings")
+            .filter(|s| s.len() == 4)
+            .map(|s| SquadSettings {
+                allow_body_collisions: s[0] != 0,
+                shared_elimination: s[1] != 0,
+                shared_health: s[2] != 0,
+                shared_length: s[3] != 0,
+            });
 
         let mut healths = [0; MAX_SNAKES];
         let healths_iter = hash.get("healths").unwrap().iter().map(|x| *x as u8);
@@ -156,20 +313,31 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         }
 
         let mut cells = [Cell::<T>::empty(); BOARD_SIZE];
-        let cells_iter = hash.get("cells").unwrap().iter().map(|x| *x as u32);
+        let cells_iter = hash.get("cells").unwrap().iter().copied();
         for (idx, cell) in cells_iter.enumerate() {
             cells[idx] = Cell::<T>::from_u32(cell);
         }
 
+        let mut under = [Cell::<T>::empty(); BOARD_SIZE];
+        let under_iter = hash.get("under").into_iter().flatten().copied();
+        for (idx, cell) in under_iter.enumerate() {
+            under[idx] = Cell::<T>::from_u32(cell);
+        }
+
         let dimensions = D::from_dimensions(actual_width, actual_height);
 
         CellBoard {
             hazard_damage,
             cells,
+            under,
             healths,
             heads,
             lengths,
             dimensions,
+            solo,
+            squads,
+            squad_settings,
+            eliminated_last_turn,
         }
     }
 
@@ -206,9 +374,9 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             } else {
                 panic!("We should never get here");
             }
-            CellIndex::<T>::new(new_head_position, Self::width())
+            CellIndex::<T>::new(new_head_position, self.get_actual_width())
         } else {
-            CellIndex::<T>::new(new_head_position, Self::width())
+            CellIndex::<T>::new(new_head_position, self.get_actual_width())
         }
     }
 
@@ -228,14 +396,11 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
 
     fn kill_and_remove(&mut self, sid: SnakeId) {
         let head = self.heads[sid.as_usize()];
-        let mut current_index = self.get_cell(head).get_tail_position(head);
+        let mut current_index = self.get_snake_cell(head, sid).get_tail_position(head);
 
         while let Some(i) = current_index {
-            current_index = self.get_cell(i).get_next_index();
-            debug_assert!(
-                self.get_cell(i).get_snake_id().unwrap_or(sid).as_usize() == sid.as_usize()
-            );
-            self.cell_remove(i);
+            current_index = self.get_snake_cell(i, sid).get_next_index();
+            self.remove_snake_piece(i, sid);
         }
 
         self.kill(sid);
@@ -255,14 +420,20 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
 
         for snake in &game.board.snakes {
             let counts = &snake.body.iter().counts();
-            if counts.values().any(|v| *v == TRIPLE_STACK) && counts.len() != 1 {
-                return Err(format!("snake {} has a bad body stack (3 segs on same square and more than one unique position)", snake.id).into());
+            // only a growing tail can be stacked more than twice
+            let tail = snake.body.back();
+            let bad_stack = counts
+                .iter()
+                .any(|(pos, v)| *v >= TRIPLE_STACK && Some(*pos) != tail);
+            if bad_stack && counts.len() != 1 {
+                return Err(format!("snake {} has a bad body stack (3 segs on a square other than its tail and more than one unique position)", snake.id).into());
             }
         }
         let width = game.board.width as u8;
         let height = game.board.height as u8;
 
         let mut cells = [Cell::empty(); BOARD_SIZE];
+        let mut under = [Cell::empty(); BOARD_SIZE];
         let mut healths: [u8; MAX_SNAKES] = [0; MAX_SNAKES];
         let mut heads: [CellIndex<T>; MAX_SNAKES] = [CellIndex::from_i32(0); MAX_SNAKES];
         let mut lengths: [u16; MAX_SNAKES] = [0; MAX_SNAKES];
@@ -290,7 +461,14 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
                     assert!(cell_idx == head_idx);
                     heads[snake_id.0 as usize] = head_idx;
                 }
-                cells[cell_idx.0.as_usize()] = if *count == TRIPLE_STACK {
+                // teammates that moved through each other share a cell, checked by
+                // `squads_from_game`. The segment that got there second goes underneath
+                let cell = if cells[cell_idx.0.as_usize()].get_snake_id().is_some() {
+                    &mut under[cell_idx.0.as_usize()]
+                } else {
+                    &mut cells[cell_idx.0.as_usize()]
+                };
+                *cell = if *count == TRIPLE_STACK && *pos == snake.head {
                     Cell::make_triple_stacked_piece(snake_id)
                 } else if *pos == snake.head {
                     // head can never be doubled, so let's assert it here, the cost of
@@ -298,7 +476,8 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
                     assert!(*count != DOUBLE_STACK);
                     let tail_index = CellIndex::new(*snake.body.back().unwrap(), width);
                     Cell::make_snake_head(snake_id, tail_index)
-                } else if *count == DOUBLE_STACK {
+                } else if *count >= DOUBLE_STACK {
+                    // a tail can only be stacked once, the length keeps track of the rest
                     Cell::make_double_stacked_piece(snake_id, next_index)
                 } else {
                     Cell::make_body_piece(snake_id, next_index)
@@ -313,9 +492,15 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
                     y: y as i32,
                 };
                 let cell_idx: CellIndex<T> = CellIndex::new(position, width);
-                if game.board.hazards.contains(&position) {
-                    cells[cell_idx.0.as_usize()].set_hazard();
-                }
+                // the engine repeats positions in the hazard list when hazards stack
+                let hazard_count = game
+                    .board
+                    .hazards
+                    .iter()
+                    .filter(|h| **h == position)
+                    .count();
+                cells[cell_idx.0.as_usize()]
+                    .set_hazard_count(hazard_count.min(u8::MAX as usize) as u8);
                 if game.board.food.contains(&position) {
                     cells[cell_idx.0.as_usize()].set_food();
                 }
@@ -323,9 +508,13 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         }
 
         let dimensions = D::from_dimensions(width, height);
+        let solo = game.game.ruleset.name == "solo";
+        let squad_settings = game.get_squad_settings();
+        let squads = Self::squads_from_game(&game, snake_ids)?;
 
         Ok(CellBoard {
             cells,
+            under,
             heads,
             healths,
             lengths,
@@ -336,13 +525,106 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
                 .settings
                 .as_ref()
                 .map(|s| s.hazard_damage_per_turn)
-                .unwrap_or(15) as u8,
+                .unwrap_or(15)
+                // damage outside of this range kills or fully heals in one turn anyway
+                .clamp(i8::MIN as i32, i8::MAX as i32) as i8,
+            solo,
+            squads,
+            squad_settings,
+            eliminated_last_turn: [false; MAX_SNAKES],
         })
     }
+    /// numbers each squad in the game from 1, in the order they appear
+    fn squads_from_game(
+        game: &Game,
+        snake_ids: &SnakeIDMap,
+    ) -> Result<[u8; MAX_SNAKES], Box<dyn Error>> {
+        let mut squads = [0; MAX_SNAKES];
+        if !game.is_squad() {
+            return Ok(squads);
+        }
+
+        let squad_names = game
+            .board
+            .snakes
+            .iter()
+            .filter_map(|s| s.squad.as_ref())
+            .unique()
+            .collect_vec();
+        for snake in &game.board.snakes {
+            let (snake_id, squad) = match (get_snake_id(snake, snake_ids), &snake.squad) {
+                (Some(snake_id), Some(squad)) => (snake_id, squad),
+                _ => continue,
+            };
+            let squad_number = squad_names.iter().position(|s| *s == squad).unwrap() + 1;
+            squads[snake_id.as_usize()] = squad_number as u8;
+        }
+
+        // teammates can move through each other when body collisions are allowed, but a cell
+        // only has room for two snakes
+        let allow_body_collisions = game
+            .get_squad_settings()
+            .map_or(false, |s| s.allow_body_collisions);
+        let mut occupied: HashMap<Position, Vec<&BattleSnake>> = HashMap::new();
+        for snake in game.board.snakes.iter().filter(|s| s.health > 0) {
+            for pos in snake.body.iter().unique() {
+                let occupants = occupied.entry(*pos).or_default();
+                let shares_with_teammate = occupants
+                    .iter()
+                    .all(|other| other.squad.is_some() && other.squad == snake.squad);
+                if let Some(other) = occupants.first() {
+                    if !allow_body_collisions || !shares_with_teammate || occupants.len() > 1 {
+                        return Err(format!(
+                            "snakes {} and {} overlap at {:?}, which compact boards can't represent",
+                            other.id, snake.id, pos
+                        )
+                        .into());
+                    }
+                }
+                occupants.push(snake);
+            }
+        }
+
+        Ok(squads)
+    }
+
     fn get_cell(&self, cell_index: CellIndex<T>) -> Cell<T> {
         self.cells[cell_index.0.as_usize()]
     }
 
+    /// gets the cell holding the given snake's segment at this index, which is the one
+    /// underneath when the snake moved through a teammate there
+    fn get_snake_cell(&self, cell_index: CellIndex<T>, sid: SnakeId) -> Cell<T> {
+        let under = self.under[cell_index.0.as_usize()];
+        if under.get_snake_id() == Some(sid) {
+            under
+        } else {
+            self.get_cell(cell_index)
+        }
+    }
+
+    /// the cell to lay the given snake's segment in at this index. That's the one underneath
+    /// when a teammate's segment is already on top
+    fn snake_cell_mut(&mut self, cell_index: CellIndex<T>, sid: SnakeId) -> &mut Cell<T> {
+        let idx = cell_index.0.as_usize();
+        let top = self.cells[idx].get_snake_id();
+        if self.under[idx].get_snake_id() == Some(sid) || top.map_or(false, |id| id != sid) {
+            &mut self.under[idx]
+        } else {
+            &mut self.cells[idx]
+        }
+    }
+
+    /// removes the given snake's segment from this index, leaving any teammate's segment there
+    fn remove_snake_piece(&mut self, cell_index: CellIndex<T>, sid: SnakeId) {
+        let idx = cell_index.0.as_usize();
+        if self.under[idx].get_snake_id() == Some(sid) {
+            self.under[idx] = Cell::empty();
+        } else {
+            self.cell_remove(cell_index);
+        }
+    }
+
     /// determines if a given position is not on the board
     pub fn off_board(&self, position: Position) -> bool {
         position.x < 0
@@ -355,11 +637,19 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     pub fn get_length(&self, snake_id: SnakeId) -> u16 {
         self.lengths[snake_id.0 as usize]
     }
-    /// Mutibaly call remove on the specified cell
+    /// Mutibaly call remove on the specified cell, moving up any segment that was underneath
     pub fn cell_remove(&mut self, cell_index: CellIndex<T>) {
+        let idx = cell_index.0.as_usize();
         let mut old_cell = self.get_cell(cell_index);
         old_cell.remove();
-        self.cells[cell_index.0.as_usize()] = old_cell;
+        let under = std::mem::replace(&mut self.under[idx], Cell::empty());
+        if !under.is_empty() {
+            // hazards are only kept on top
+            let hazard_count = old_cell.get_hazard_count();
+            old_cell = under;
+            old_cell.set_hazard_count(hazard_count);
+        }
+        self.cells[idx] = old_cell;
     }
     /// Set the given index to a Snake Body Piece
     pub fn set_cell_body_piece(
@@ -368,9 +658,8 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         sid: SnakeId,
         next_id: CellIndex<T>,
     ) {
-        let mut old_cell = self.get_cell(cell_index);
-        old_cell.set_body_piece(sid, next_id);
-        self.cells[cell_index.0.as_usize()] = old_cell;
+        self.snake_cell_mut(cell_index, sid)
+            .set_body_piece(sid, next_id);
     }
 
     /// Set the given index as a double stacked snake
@@ -380,9 +669,8 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         sid: SnakeId,
         next_id: CellIndex<T>,
     ) {
-        let mut old_cell = self.get_cell(cell_index);
-        old_cell.set_double_stacked(sid, next_id);
-        self.cells[cell_index.0.as_usize()] = old_cell;
+        self.snake_cell_mut(cell_index, sid)
+            .set_double_stacked(sid, next_id);
     }
 
     /// Set the given index as a snake head
@@ -392,9 +680,8 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         sid: SnakeId,
         next_id: CellIndex<T>,
     ) {
-        let mut old_cell = self.get_cell(old_head_index);
-        old_cell.set_head(sid, next_id);
-        self.cells[old_head_index.0.as_usize()] = old_cell;
+        self.snake_cell_mut(old_head_index, sid)
+            .set_head(sid, next_id);
     }
 
     /// gets the snake ID at a given index, returns None if the provided index is not a snake cell
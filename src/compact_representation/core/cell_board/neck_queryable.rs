@@ -9,7 +9,7 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
     fn is_neck(&self, sid: &Self::SnakeIDType, pos: &Self::NativePositionType) -> bool {
-        let potential_neck = self.get_cell(*pos);
+        let potential_neck = self.get_snake_cell(*pos, *sid);
 
         potential_neck.get_snake_id() == Some(*sid)
             && potential_neck.get_next_index() == Some(self.get_head_as_native_position(sid))
@@ -17,7 +17,7 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     fn position_is_snake_body(&self, pos: Self::NativePositionType) -> bool {
         let cell = self.get_cell(pos);
 
-        cell.is_body_segment()
+        cell.is_body_segment() || self.under[pos.0.as_usize()].is_body_segment()
     }
 
     fn position_from_native(&self, pos: Self::NativePositionType) -> Position {
@@ -31,6 +31,9 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     }
 
     fn off_board(&self, pos: Position) -> bool {
-        pos.x < 0 || pos.x >= self.get_actual_width() as i32 || pos.y < 0 || pos.y >= self.get_actual_height() as i32
+        pos.x < 0
+            || pos.x >= self.get_actual_width() as i32
+            || pos.y < 0
+            || pos.y >= self.get_actual_height() as i32
     }
 }
@@ -9,22 +9,30 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
     SnakeBodyGettableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
     fn get_snake_body_vec(&self, snake_id: &Self::SnakeIDType) -> Vec<Self::NativePositionType> {
-        let mut body = vec![];
-        body.reserve(self.get_length(*snake_id).into());
+        let mut body = Vec::with_capacity(self.get_length(*snake_id).into());
         let head = self.get_head_as_native_position(snake_id);
 
-        let mut cur = Some(self.get_cell(head).get_tail_position(head).unwrap());
+        let tail = self
+            .get_snake_cell(head, *snake_id)
+            .get_tail_position(head)
+            .unwrap();
+        // segments the cells don't hold yet are stacked on the tail
+        let still_growing = self.get_length(*snake_id) - self.represented_length(*snake_id);
+        body.extend(std::iter::repeat(tail).take(still_growing.into()));
+
+        let mut cur = Some(tail);
 
         while let Some(c) = cur {
             body.push(c);
-            if self.get_cell(c).is_double_stacked_piece() {
+            let cell = self.get_snake_cell(c, *snake_id);
+            if cell.is_double_stacked_piece() {
                 body.push(c);
             }
-            if self.get_cell(c).is_triple_stacked_piece() {
+            if cell.is_triple_stacked_piece() {
                 body.push(c);
                 body.push(c);
             }
-            cur = self.get_cell(c).get_next_index();
+            cur = cell.get_next_index();
         }
 
         body.reverse();
@@ -36,14 +44,19 @@ impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize
         &'s self,
         snake_id: &Self::SnakeIDType,
     ) -> Box<dyn Iterator<Item = Self::NativePositionType> + 's> {
-        let head = self.get_head_as_native_position(snake_id);
+        let snake_id = *snake_id;
+        let head = self.get_head_as_native_position(&snake_id);
 
-        let mut cur = Some(self.get_cell(head).get_tail_position(head).unwrap());
+        let mut cur = Some(
+            self.get_snake_cell(head, snake_id)
+                .get_tail_position(head)
+                .unwrap(),
+        );
 
         Box::new(std::iter::from_fn(move || {
             if let Some(c) = cur {
                 let to_return = c;
-                cur = self.get_cell(c).get_next_index();
+                cur = self.get_snake_cell(c, snake_id).get_next_index();
 
                 Some(to_return)
             } else {
@@ -0,0 +1,20 @@
+use crate::{
+    compact_representation::{core::dimensions::Dimensions, CellNum},
+    types::SquadQueryableGame,
+    wire_representation::SquadSettings,
+};
+
+use super::CellBoard;
+
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> SquadQueryableGame
+    for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn get_squad_settings(&self) -> Option<SquadSettings> {
+        self.squad_settings
+    }
+
+    fn is_teammate(&self, a: &Self::SnakeIDType, b: &Self::SnakeIDType) -> bool {
+        let squad = self.squads[a.as_usize()];
+        a != b && squad != 0 && squad == self.squads[b.as_usize()]
+    }
+}
@@ -1,40 +1,64 @@
-use itertools::Itertools;
-
 use crate::{
     compact_representation::{core::dimensions::Dimensions, CellNum},
-    types::{SnakeId, VictorDeterminableGame},
+    types::{GameOutcome, SnakeId, SquadQueryableGame, VictorDeterminableGame},
 };
 
 use super::CellBoard;
 
+impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// how many squads still have a snake alive, counting each snake without a squad on its own
+    fn alive_squad_count(&self) -> usize {
+        (0..MAX_SNAKES)
+            .filter(|i| self.healths[*i] != 0)
+            .filter(|i| {
+                // only count the first alive snake of each squad
+                !(0..*i).any(|j| {
+                    self.healths[j] != 0 && self.is_teammate(&SnakeId(*i as u8), &SnakeId(j as u8))
+                })
+            })
+            .count()
+    }
+
+    /// how many snakes, or squads in squad games, are still alive
+    fn remaining_snakes_or_squads(&self) -> usize {
+        if self.squad_settings.is_some() {
+            self.alive_squad_count()
+        } else {
+            self.alive_snake_count()
+        }
+    }
+}
+
 impl<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     VictorDeterminableGame for CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>
 {
     fn is_over(&self) -> bool {
-        self.healths[0] == 0 || self.healths.iter().filter(|h| **h != 0).count() <= 1
+        let remaining = self.remaining_snakes_or_squads();
+        self.healths[0] == 0 || remaining == 0 || (remaining == 1 && !self.solo)
     }
 
-    fn get_winner(&self) -> Option<Self::SnakeIDType> {
-        if self.is_over() {
-            let winning_ids = self
-                .healths
-                .iter()
-                .enumerate()
-                .filter_map(|(id, health)| {
-                    if *health != 0 {
-                        Some(SnakeId(id as u8))
-                    } else {
-                        None
-                    }
-                })
-                .collect_vec();
-            if winning_ids.is_empty() {
-                return None;
-            } else {
-                return Some(winning_ids[0]);
-            }
+    fn get_outcome(&self) -> GameOutcome<Self::SnakeIDType> {
+        let remaining = self.remaining_snakes_or_squads();
+
+        if remaining == 0 && self.solo {
+            GameOutcome::SoloEnded
+        } else if remaining == 0 {
+            let eliminated = (0..MAX_SNAKES)
+                .filter(|i| self.eliminated_last_turn[*i])
+                .map(|i| SnakeId(i as u8))
+                .collect();
+            GameOutcome::Draw(eliminated)
+        } else if remaining == 1 && !self.solo {
+            // the lowest alive id, which is you if you are on the winning squad
+            let winner = (0..MAX_SNAKES).find(|i| self.healths[*i] != 0).unwrap();
+            GameOutcome::Win(SnakeId(winner as u8))
+        } else if self.healths[0] == 0 {
+            GameOutcome::Eliminated
+        } else {
+            GameOutcome::Ongoing
         }
-        None
     }
 
     fn alive_snake_count(&self) -> usize {
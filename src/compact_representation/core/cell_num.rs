@@ -2,7 +2,17 @@ use std::fmt::Display;
 
 /// Wrapper type for numbers to allow for shrinking board sizes
 pub trait CellNum:
-    std::fmt::Debug + Copy + Clone + PartialEq + Eq + std::hash::Hash + Ord + Display + 'static
+    std::fmt::Debug
+    + Copy
+    + Clone
+    + PartialEq
+    + Eq
+    + std::hash::Hash
+    + Ord
+    + Display
+    + Send
+    + Sync
+    + 'static
 {
     /// converts this cellnum to a usize
     fn as_usize(&self) -> usize;
@@ -16,6 +16,18 @@ macro_rules! impl_common_board_traits {
             }
         }
 
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            SquadQueryableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn get_squad_settings(&self) -> Option<$crate::wire_representation::SquadSettings> {
+                self.embedded.get_squad_settings()
+            }
+
+            fn is_teammate(&self, a: &Self::SnakeIDType, b: &Self::SnakeIDType) -> bool {
+                self.embedded.is_teammate(a, b)
+            }
+        }
+
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
             HealthGettableGame for $type<T, D, BOARD_SIZE, MAX_SNAKES>
         {
@@ -38,8 +50,8 @@ macro_rules! impl_common_board_traits {
                 self.embedded.is_over()
             }
 
-            fn get_winner(&self) -> Option<Self::SnakeIDType> {
-                self.embedded.get_winner()
+            fn get_outcome(&self) -> GameOutcome<Self::SnakeIDType> {
+                self.embedded.get_outcome()
             }
 
             fn alive_snake_count(&self) -> usize {
@@ -96,9 +108,17 @@ macro_rules! impl_common_board_traits {
                 self.embedded.set_hazard(pos)
             }
 
+            fn add_hazard(&mut self, pos: Self::NativePositionType) {
+                self.embedded.add_hazard(pos)
+            }
+
             fn clear_hazard(&mut self, pos: Self::NativePositionType) {
                 self.embedded.clear_hazard(pos)
             }
+
+            fn remove_hazard(&mut self, pos: Self::NativePositionType) {
+                self.embedded.remove_hazard(pos)
+            }
         }
 
         impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
@@ -108,7 +128,11 @@ macro_rules! impl_common_board_traits {
                 self.embedded.is_hazard(pos)
             }
 
-            fn get_hazard_damage(&self) -> u8 {
+            fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+                self.embedded.get_hazard_count(pos)
+            }
+
+            fn get_hazard_damage(&self) -> i32 {
                 self.embedded.get_hazard_damage()
             }
         }
@@ -209,5 +233,63 @@ macro_rules! impl_common_board_traits {
                 $type::convert_from_game(game, &id_map)
             }
         }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            /// Parses a board from an ascii grid, see `wire_representation::ascii` for the
+            /// format. Snakes named by digits keep those digits as their snake ids
+            pub fn from_ascii(s: &str) -> Result<Self, Box<dyn Error>> {
+                let (game, snake_ids) = Game::from_ascii_with_snake_ids(s)?;
+                if snake_ids.values().any(|id| id.as_usize() >= MAX_SNAKES) {
+                    return Err("too many snakes".into());
+                }
+
+                $type::convert_from_game(game, &snake_ids)
+            }
+        }
+
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            /// Attaches a hazard algorithm wound to this board's turn, so simulating the board
+            /// also sets the hazards the algorithm spawns, see `hazard_algorithms::simulation`
+            pub fn with_hazard_algorithm<H>(
+                self,
+                hazards: H,
+            ) -> $crate::hazard_algorithms::simulation::HazardForecastingGame<Self, H>
+            where
+                H: $crate::hazard_algorithms::ForwardOnlyHazardAlgorithm<
+                    $crate::wire_representation::Position,
+                >,
+            {
+                $crate::hazard_algorithms::simulation::HazardForecastingGame::new(self, hazards)
+            }
+        }
+
+        /// serializes as the packed hash representation, see `pack_as_hash`
+        impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            serde::Serialize for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serde::Serialize::serialize(&self.embedded.pack_as_hash(), serializer)
+            }
+        }
+
+        impl<'de, T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+            serde::Deserialize<'de> for $type<T, D, BOARD_SIZE, MAX_SNAKES>
+        {
+            fn deserialize<De: serde::Deserializer<'de>>(
+                deserializer: De,
+            ) -> Result<Self, De::Error> {
+                let hash: std::collections::HashMap<String, Vec<u32>> =
+                    serde::Deserialize::deserialize(deserializer)?;
+                CCB::<T, D, BOARD_SIZE, MAX_SNAKES>::validate_packed_hash(&hash)
+                    .map_err(serde::de::Error::custom)?;
+                Ok(Self {
+                    embedded: CCB::from_packed_hash(&hash),
+                })
+            }
+        }
     };
 }
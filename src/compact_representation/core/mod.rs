@@ -10,7 +10,12 @@ use crate::{
 
 pub use cell_board::{CellBoard, EvaluateMode};
 pub use cell_num::CellNum;
-pub use simulate::simulate_with_moves;
+#[cfg(feature = "rayon")]
+pub use simulate::par_simulate_with_moves;
+pub use simulate::{
+    apply_action, apply_action_with_eliminations, simulate_with_moves,
+    simulate_with_moves_and_eliminations, EliminatingStates, SimulatedStates,
+};
 
 /// wrapper type for an index in to the board
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
@@ -45,8 +50,8 @@ impl<T: CellNum> CellIndex<T> {
 
     /// converts a cellindex to a position
     pub fn into_position(self, width: u8) -> Position {
-        let y = (self.0.as_usize() as i32 / width as i32) as i32;
-        let x = (self.0.as_usize() as i32 % width as i32) as i32;
+        let y = self.0.as_usize() as i32 / width as i32;
+        let x = self.0.as_usize() as i32 % width as i32;
         Position { x, y }
     }
 
@@ -64,7 +69,13 @@ const FOOD: u8 = 0x04;
 const EMPTY: u8 = 0x05;
 const KIND_MASK: u8 = 0x07;
 
-const IS_HAZARD: u8 = 0x10;
+// the upper nibble of the flags holds how many hazards are stacked on a cell. A single hazard
+// is 0x10, which keeps boards packed before hazards could stack readable
+const HAZARD_SHIFT: u8 = 4;
+const HAZARD_MASK: u8 = 0xf0;
+
+/// the most hazards that can be stacked on a single cell
+pub const MAX_HAZARD_STACK: u8 = HAZARD_MASK >> HAZARD_SHIFT;
 
 pub const TRIPLE_STACK: usize = 3;
 pub const DOUBLE_STACK: usize = 2;
@@ -125,16 +136,40 @@ impl<T: CellNum> Cell<T> {
         self.flags & KIND_MASK == FOOD
     }
 
+    /// makes this cell a hazard, leaving it alone if it already has hazards on it
     pub fn set_hazard(&mut self) {
-        self.flags |= IS_HAZARD
+        if !self.is_hazard() {
+            self.set_hazard_count(1);
+        }
+    }
+
+    /// stacks another hazard on this cell, saturating at [MAX_HAZARD_STACK]
+    pub fn add_hazard(&mut self) {
+        self.set_hazard_count(self.get_hazard_count().saturating_add(1));
     }
 
     pub fn clear_hazard(&mut self) {
-        self.flags &= !IS_HAZARD
+        self.flags &= !HAZARD_MASK
+    }
+
+    /// takes one hazard off the stack on this cell
+    pub fn remove_hazard(&mut self) {
+        self.set_hazard_count(self.get_hazard_count().saturating_sub(1));
     }
 
     pub fn is_hazard(&self) -> bool {
-        self.flags & IS_HAZARD != 0
+        self.flags & HAZARD_MASK != 0
+    }
+
+    /// how many hazards are stacked on this cell
+    pub fn get_hazard_count(&self) -> u8 {
+        (self.flags & HAZARD_MASK) >> HAZARD_SHIFT
+    }
+
+    /// sets how many hazards are stacked on this cell, saturating at [MAX_HAZARD_STACK]
+    pub fn set_hazard_count(&mut self, count: u8) {
+        let count = count.min(MAX_HAZARD_STACK);
+        self.flags = (self.flags & !HAZARD_MASK) | (count << HAZARD_SHIFT);
     }
 
     pub fn is_body_segment(&self) -> bool {
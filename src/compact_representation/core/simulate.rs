@@ -1,10 +1,202 @@
 use std::{borrow::Borrow, time::Instant};
 
-use itertools::Itertools;
+use crate::types::{Action, EliminationCause, Move, SimulatorInstruments, SnakeId, N_MOVES};
 
-use crate::types::{Action, Move, SimulatorInstruments, SnakeId, N_MOVES};
+use super::{
+    cell_board::{EvaluateMode, SinglePlayerMoveResult},
+    dimensions::Dimensions,
+    CellBoard, CellNum,
+};
 
-use super::{cell_board::EvaluateMode, dimensions::Dimensions, CellBoard, CellNum};
+type MoveResults<T, const MAX_SNAKES: usize> = [[SinglePlayerMoveResult<T>; N_MOVES]; MAX_SNAKES];
+type Eliminations<const MAX_SNAKES: usize> = [Option<EliminationCause<SnakeId>>; MAX_SNAKES];
+
+/// Iterator over the child states of a compact board, returned from simulating it.
+///
+/// At most `MAX_SNAKES` snakes are simulated, entries past that are ignored, and each snake's
+/// moves are deduplicated so that it never has more than four.
+///
+/// Everything needed to produce the children is stored inline, so creating and advancing this
+/// iterator never allocates. Children are produced in the same order as the cartesian product of
+/// the moves for each snake, with the last snake's moves changing fastest.
+#[derive(Debug, Clone)]
+pub struct SimulatedStates<
+    'a,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+> {
+    board: &'a CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    states: MoveResults<T, MAX_SNAKES>,
+    snake_ids: [SnakeId; MAX_SNAKES],
+    moves: [[Move; N_MOVES]; MAX_SNAKES],
+    move_counts: [usize; MAX_SNAKES],
+    n_snakes: usize,
+    /// the index in to `moves` for each snake of the next joint move to produce
+    cursor: [usize; MAX_SNAKES],
+    done: bool,
+}
+
+impl<'a, T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    SimulatedStates<'a, T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    fn new<S>(
+        board: &'a CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+        evaluate_mode: EvaluateMode,
+    ) -> Self
+    where
+        S: Borrow<[Move]>,
+    {
+        let mut snake_ids = [SnakeId(0); MAX_SNAKES];
+        let mut moves = [[Move::Up; N_MOVES]; MAX_SNAKES];
+        let mut move_counts = [0; MAX_SNAKES];
+        let mut n_snakes = 0;
+
+        // there is only room for MAX_SNAKES snakes with N_MOVES moves each, so any further snakes
+        // are ignored and a move listed twice is only simulated once
+        for (snake_id, mvs) in snake_ids_and_moves.into_iter().take(MAX_SNAKES) {
+            snake_ids[n_snakes] = snake_id;
+            for mv in mvs.borrow() {
+                if moves[n_snakes][..move_counts[n_snakes]].contains(mv) {
+                    continue;
+                }
+                moves[n_snakes][move_counts[n_snakes]] = *mv;
+                move_counts[n_snakes] += 1;
+            }
+            n_snakes += 1;
+        }
+
+        // [
+        // sid major, move minor
+        // [ some_reulst_struct, some_dead_struct ]
+        // [ some_dead_struct, some_dead_struct ] // snake we didn't simulate
+        let states = board.generate_state(
+            (0..n_snakes).map(|i| (snake_ids[i], &moves[i][..move_counts[i]])),
+            evaluate_mode,
+        );
+
+        // only keep the moves that don't kill the snake right away, unless every move does in
+        // which case we keep the first one
+        for i in 0..n_snakes {
+            let sid = snake_ids[i].as_usize();
+            let first_move = moves[i][0];
+            let mut alive_count = 0;
+            for j in 0..move_counts[i] {
+                let mv = moves[i][j];
+                if !states[sid][mv.as_index()].is_dead() {
+                    moves[i][alive_count] = mv;
+                    alive_count += 1;
+                }
+            }
+            move_counts[i] = if alive_count == 0 {
+                moves[i][0] = first_move;
+                1
+            } else {
+                alive_count
+            };
+        }
+
+        Self {
+            board,
+            states,
+            snake_ids,
+            moves,
+            move_counts,
+            n_snakes,
+            cursor: [0; MAX_SNAKES],
+            done: n_snakes == 0,
+        }
+    }
+
+    /// produces the next combination of moves to evaluate, and how many snakes are in it
+    fn next_joint_move(&mut self) -> Option<([(SnakeId, Move); MAX_SNAKES], usize)> {
+        if self.done {
+            return None;
+        }
+
+        let mut joint = [(SnakeId(0), Move::Up); MAX_SNAKES];
+        for (i, slot) in joint.iter_mut().enumerate().take(self.n_snakes) {
+            *slot = (self.snake_ids[i], self.moves[i][self.cursor[i]]);
+        }
+
+        // advance the cursor like an odometer, last snake first
+        self.done = true;
+        for i in (0..self.n_snakes).rev() {
+            self.cursor[i] += 1;
+            if self.cursor[i] < self.move_counts[i] {
+                self.done = false;
+                break;
+            }
+            self.cursor[i] = 0;
+        }
+
+        Some((joint, self.n_snakes))
+    }
+}
+
+impl<'a, T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Iterator
+    for SimulatedStates<'a, T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    type Item = (Action<MAX_SNAKES>, CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (joint, n_snakes) = self.next_joint_move()?;
+
+        let (action, game, _) = evaluate(self.board, &joint[..n_snakes], &self.states);
+        Some((action, game))
+    }
+}
+
+/// Same as [SimulatedStates], but each child state also comes with the cause of elimination for
+/// every snake the move that produced it eliminated, indexed by snake id
+#[derive(Debug, Clone)]
+pub struct EliminatingStates<
+    'a,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(SimulatedStates<'a, T, D, BOARD_SIZE, MAX_SNAKES>);
+
+impl<'a, T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize> Iterator
+    for EliminatingStates<'a, T, D, BOARD_SIZE, MAX_SNAKES>
+{
+    type Item = (
+        Action<MAX_SNAKES>,
+        CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+        Eliminations<MAX_SNAKES>,
+    );
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (joint, n_snakes) = self.0.next_joint_move()?;
+
+        Some(evaluate(self.0.board, &joint[..n_snakes], &self.0.states))
+    }
+}
+
+/// evaluates a single combination of moves, panicking if the result is inconsistent
+fn evaluate<T: CellNum, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>(
+    board: &CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    m: &[(SnakeId, Move)],
+    states: &MoveResults<T, MAX_SNAKES>,
+) -> (
+    Action<MAX_SNAKES>,
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    Eliminations<MAX_SNAKES>,
+) {
+    let action = Action::collect_from(m.iter());
+
+    let (game, eliminations) = board.evaluate_moves_with_state_and_eliminations(m, states);
+    if !game.assert_consistency() {
+        panic!(
+            "caught an inconsistent simulate, moves: {:?} orig: {}, new: {}",
+            m, board, game
+        );
+    }
+    (action, game, eliminations)
+}
 
 pub fn simulate_with_moves<
     'a,
@@ -19,62 +211,131 @@ pub fn simulate_with_moves<
     instruments: &I,
     snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
     evaluate_mode: EvaluateMode,
-) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>)> + 'a>
+) -> SimulatedStates<'a, T, D, BOARD_SIZE, MAX_SNAKES>
 where
     S: Borrow<[Move]>,
 {
     let start = Instant::now();
-    let snake_ids_and_moves = snake_ids_and_moves.into_iter().collect_vec();
+    let return_value = SimulatedStates::new(board, snake_ids_and_moves, evaluate_mode);
+    let end = Instant::now();
+    instruments.observe_simulation(end - start);
+    return_value
+}
 
-    let mut snake_ids_we_are_simulating = [false; MAX_SNAKES];
-    for (snake_id, _) in snake_ids_and_moves.iter() {
-        snake_ids_we_are_simulating[snake_id.0.as_usize()] = true;
-    }
+/// Same as [simulate_with_moves], but also returns why each snake eliminated in a child state died
+pub fn simulate_with_moves_and_eliminations<
+    'a,
+    S,
+    I: SimulatorInstruments,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    board: &'a CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    instruments: &I,
+    snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    evaluate_mode: EvaluateMode,
+) -> EliminatingStates<'a, T, D, BOARD_SIZE, MAX_SNAKES>
+where
+    S: Borrow<[Move]>,
+{
+    EliminatingStates(simulate_with_moves(
+        board,
+        instruments,
+        snake_ids_and_moves,
+        evaluate_mode,
+    ))
+}
 
-    // [
-    // sid major, move minor
-    // [ some_reulst_struct, some_dead_struct ]
-    // [ some_dead_struct, some_dead_struct ] // snake we didn't simulate
-    let states = board.generate_state(snake_ids_and_moves.iter(), evaluate_mode);
-    let mut dead_snakes_table = [[false; N_MOVES]; MAX_SNAKES];
+/// Evaluates a single joint move, only generating the result of the chosen move for each snake
+/// instead of all four
+pub fn apply_action<
+    I: SimulatorInstruments,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    board: &CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    instruments: &I,
+    action: &Action<MAX_SNAKES>,
+    evaluate_mode: EvaluateMode,
+) -> CellBoard<T, D, BOARD_SIZE, MAX_SNAKES> {
+    apply_action_with_eliminations(board, instruments, action, evaluate_mode).0
+}
 
-    for (sid, result_row) in states.iter().enumerate() {
-        for (move_index, move_result) in result_row.iter().enumerate() {
-            dead_snakes_table[sid][move_index] = move_result.is_dead();
-        }
+/// Same as [apply_action], but also returns why each snake eliminated by the action died
+pub fn apply_action_with_eliminations<
+    I: SimulatorInstruments,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    board: &CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    instruments: &I,
+    action: &Action<MAX_SNAKES>,
+    evaluate_mode: EvaluateMode,
+) -> (
+    CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    Eliminations<MAX_SNAKES>,
+) {
+    let start = Instant::now();
+    let mut joint = [(SnakeId(0), Move::Up); MAX_SNAKES];
+    let mut n_snakes = 0;
+    for (slot, id_and_move) in joint.iter_mut().zip(action.iter()) {
+        *slot = id_and_move;
+        n_snakes += 1;
     }
+    let joint = &joint[..n_snakes];
 
-    let ids_and_moves_product = snake_ids_and_moves
-        .into_iter()
-        .map(|(snake_id, moves)| {
-            let first_move = moves.borrow()[0];
-            let mvs = moves
-                .borrow()
-                .iter()
-                .filter(|mv| !dead_snakes_table[snake_id.0 as usize][mv.as_index()])
-                .map(|mv| (snake_id, *mv))
-                .collect_vec();
-            if mvs.is_empty() {
-                vec![(snake_id, first_move)]
-            } else {
-                mvs
-            }
-        })
-        .multi_cartesian_product();
-    let results = ids_and_moves_product.into_iter().map(move |m| {
-        let action = Action::collect_from(m.iter());
-
-        let game = board.evaluate_moves_with_state(m.iter(), &states);
-        if !game.assert_consistency() {
-            panic!(
-                "caught an inconsistent simulate, moves: {:?} orig: {}, new: {}",
-                m, board, game
-            );
-        }
+    let states = board.generate_state(
+        joint
+            .iter()
+            .map(|(sid, mv)| (*sid, std::slice::from_ref(mv))),
+        evaluate_mode,
+    );
+    let (_, game, eliminations) = evaluate(board, joint, &states);
+    let end = Instant::now();
+    instruments.observe_simulation(end - start);
+    (game, eliminations)
+}
+
+/// Same as [simulate_with_moves] but the child states are evaluated in parallel on the rayon
+/// thread pool. The combinations of moves are collected up front so they can be split across
+/// threads.
+#[cfg(feature = "rayon")]
+pub fn par_simulate_with_moves<
+    'a,
+    S,
+    I: SimulatorInstruments,
+    T: CellNum,
+    D: Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>(
+    board: &'a CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>,
+    instruments: &I,
+    snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    evaluate_mode: EvaluateMode,
+) -> impl rayon::iter::ParallelIterator<
+    Item = (Action<MAX_SNAKES>, CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>),
+> + 'a
+where
+    S: Borrow<[Move]>,
+{
+    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+    let start = Instant::now();
+    let mut simulated = SimulatedStates::new(board, snake_ids_and_moves, evaluate_mode);
+    let states = simulated.states;
+    let joint_moves = std::iter::from_fn(|| simulated.next_joint_move()).collect::<Vec<_>>();
+    let results = joint_moves.into_par_iter().map(move |(m, n_snakes)| {
+        let (action, game, _) = evaluate(board, &m[..n_snakes], &states);
         (action, game)
     });
-    let return_value = Box::new(results);
     let end = Instant::now();
     instruments.observe_simulation(end - start);
-    return_value
+    results
 }
@@ -0,0 +1,364 @@
+//! Differential tests of the compact simulators. Random legal positions are generated, a
+//! sequence of random actions is applied with both the compact boards and a deliberately naive
+//! reference implementation of the engine's rules working directly on the wire representation,
+//! and the boards have to agree after every step.
+
+use std::collections::{HashSet, VecDeque};
+
+use proptest::prelude::*;
+use rand::prelude::{IteratorRandom, SmallRng};
+use rand::{Rng, SeedableRng};
+
+use super::{StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11};
+use crate::game_fixture;
+use crate::types::{
+    build_snake_id_map, Action, FoodGettableGame, HealthGettableGame, Move, PositionGettableGame,
+    SimulableGame, SimulatorInstruments, SnakeBodyGettableGame, SnakeIDMap, SnakeId,
+};
+use crate::wire_representation::{BattleSnake, Game, Position, Settings};
+
+#[derive(Debug)]
+struct Instruments {}
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+/// the inputs a random position is built from, kept small so proptest can report them
+#[derive(Debug, Clone, Copy)]
+struct PositionSpec {
+    size: i32,
+    n_snakes: usize,
+    n_food: usize,
+    n_hazards: usize,
+    hazard_damage: i32,
+    n_steps: usize,
+    seed: u64,
+}
+
+fn position_spec() -> impl Strategy<Value = PositionSpec> {
+    (
+        5..=11i32,
+        1..=4usize,
+        0..=8usize,
+        0..=12usize,
+        -20..=30i32,
+        1..=12usize,
+        any::<u64>(),
+    )
+        .prop_map(
+            |(size, n_snakes, n_food, n_hazards, hazard_damage, n_steps, seed)| PositionSpec {
+                size,
+                n_snakes,
+                n_food,
+                n_hazards,
+                hazard_damage,
+                n_steps,
+                seed,
+            },
+        )
+}
+
+fn neighbours(p: Position, size: i32, wrapped: bool) -> Vec<Position> {
+    Move::all()
+        .iter()
+        .map(|mv| p.add_vec(mv.to_vector()))
+        .filter_map(|n| {
+            if wrapped {
+                Some(Position::new(n.x.rem_euclid(size), n.y.rem_euclid(size)))
+            } else if n.x < 0 || n.y < 0 || n.x >= size || n.y >= size {
+                None
+            } else {
+                Some(n)
+            }
+        })
+        .collect()
+}
+
+/// builds a random legal position: snakes are self avoiding random walks of at least 3 segments,
+/// possibly stacked at the tail, and food is only placed on empty cells. Hazards can stack, and
+/// share cells with anything
+fn random_game(spec: PositionSpec, wrapped: bool, rng: &mut SmallRng) -> Game {
+    let mut game = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+    let size = spec.size;
+    let mut occupied = HashSet::new();
+    let mut snakes = vec![];
+    for i in 0..spec.n_snakes {
+        let start = match (0..size * size)
+            .map(|idx| Position::new(idx % size, idx / size))
+            .filter(|p| !occupied.contains(p))
+            .choose(rng)
+        {
+            Some(start) => start,
+            None => break,
+        };
+        occupied.insert(start);
+        let mut body = VecDeque::from(vec![start]);
+        let target_cells = rng.gen_range(1..=8);
+        while body.len() < target_cells {
+            let tail = *body.back().unwrap();
+            match neighbours(tail, size, wrapped)
+                .into_iter()
+                .filter(|p| !occupied.contains(p))
+                .choose(rng)
+            {
+                Some(next) => {
+                    occupied.insert(next);
+                    body.push_back(next);
+                }
+                None => break,
+            }
+        }
+        let tail = *body.back().unwrap();
+        let stacked = match body.len() {
+            1 => 2,
+            2 => 1,
+            _ => rng.gen_range(0..=1),
+        };
+        for _ in 0..stacked {
+            body.push_back(tail);
+        }
+        snakes.push(BattleSnake {
+            id: format!("snake-{}", i),
+            name: format!("snake {}", i),
+            head: body[0],
+            body,
+            health: rng.gen_range(1..=100),
+            shout: None,
+            squad: None,
+            actual_length: None,
+        });
+    }
+
+    let empty = (0..size * size)
+        .map(|idx| Position::new(idx % size, idx / size))
+        .filter(|p| !occupied.contains(p));
+    let food = empty.choose_multiple(rng, spec.n_food);
+    let hazards = (0..spec.n_hazards)
+        .map(|_| Position::new(rng.gen_range(0..size), rng.gen_range(0..size)))
+        .collect();
+
+    game.you = snakes[0].clone();
+    game.board.width = size as u32;
+    game.board.height = size as u32;
+    game.board.snakes = snakes;
+    game.board.food = food;
+    game.board.hazards = hazards;
+    game.game.ruleset.name = if wrapped { "wrapped" } else { "standard" }.to_string();
+    game.game.ruleset.settings = Some(Settings {
+        food_spawn_chance: 0,
+        minimum_food: 0,
+        hazard_damage_per_turn: spec.hazard_damage,
+        hazard_map: None,
+        hazard_map_author: None,
+        royale: None,
+        squad: None,
+    });
+    game
+}
+
+/// a straightforward transcription of the engine's standard pipeline: move, reduce health,
+/// hazard damage, feed, then eliminate. Eliminated snakes are removed from the board
+fn reference_step(game: &Game, moves: &[(String, Move)], wrapped: bool) -> Game {
+    let size = game.board.width as i32;
+    let damage = game
+        .game
+        .ruleset
+        .settings
+        .as_ref()
+        .map(|s| s.hazard_damage_per_turn)
+        .unwrap_or(15);
+    let mut next = game.clone();
+    let mut eliminated = vec![false; next.board.snakes.len()];
+
+    for snake in next.board.snakes.iter_mut() {
+        let mv = moves.iter().find(|(id, _)| *id == snake.id).unwrap().1;
+        let mut head = snake.body[0].add_vec(mv.to_vector());
+        if wrapped {
+            head = Position::new(head.x.rem_euclid(size), head.y.rem_euclid(size));
+        }
+        snake.body.push_front(head);
+        snake.body.pop_back();
+        snake.head = head;
+        snake.health -= 1;
+    }
+
+    for snake in next.board.snakes.iter_mut() {
+        if next.board.food.contains(&snake.head) {
+            continue;
+        }
+        let stacked = next
+            .board
+            .hazards
+            .iter()
+            .filter(|h| **h == snake.head)
+            .count();
+        for _ in 0..stacked {
+            snake.health = (snake.health - damage).clamp(0, 100);
+        }
+    }
+
+    let mut eaten = vec![];
+    for snake in next.board.snakes.iter_mut() {
+        if next.board.food.contains(&snake.head) {
+            snake.health = 100;
+            let tail = *snake.body.back().unwrap();
+            snake.body.push_back(tail);
+            eaten.push(snake.head);
+        }
+    }
+    next.board.food.retain(|f| !eaten.contains(f));
+
+    let off_board = |p: Position| p.x < 0 || p.y < 0 || p.x >= size || p.y >= size;
+    for (idx, snake) in next.board.snakes.iter().enumerate() {
+        if snake.health <= 0 || off_board(snake.head) {
+            eliminated[idx] = true;
+        }
+    }
+
+    let mut collided = vec![false; next.board.snakes.len()];
+    for (idx, snake) in next.board.snakes.iter().enumerate() {
+        if eliminated[idx] {
+            continue;
+        }
+        for (other_idx, other) in next.board.snakes.iter().enumerate() {
+            if eliminated[other_idx] {
+                continue;
+            }
+            if other.body.iter().skip(1).any(|p| *p == snake.head) {
+                collided[idx] = true;
+            }
+            if other_idx != idx && other.head == snake.head && snake.body.len() <= other.body.len()
+            {
+                collided[idx] = true;
+            }
+        }
+    }
+
+    let mut idx = 0;
+    next.board.snakes.retain(|_| {
+        let keep = !eliminated[idx] && !collided[idx];
+        idx += 1;
+        keep
+    });
+    next
+}
+
+fn assert_matches_reference<B>(
+    compact: &B,
+    reference: &Game,
+    ids: &SnakeIDMap,
+) -> Result<(), TestCaseError>
+where
+    B: HealthGettableGame<SnakeIDType = SnakeId>
+        + SnakeBodyGettableGame
+        + FoodGettableGame
+        + PositionGettableGame,
+{
+    for (name, sid) in ids {
+        match reference.board.snakes.iter().find(|s| s.id == *name) {
+            Some(snake) => {
+                prop_assert_eq!(compact.get_health_i64(sid), snake.health as i64, "{}", name);
+                let body = compact
+                    .get_snake_body_vec(sid)
+                    .into_iter()
+                    .map(|p| compact.position_from_native(p))
+                    .collect::<Vec<_>>();
+                prop_assert_eq!(&body, &Vec::from(snake.body.clone()), "{}", name);
+            }
+            None => prop_assert_eq!(compact.get_health_i64(sid), 0, "{} should be dead", name),
+        }
+    }
+    let mut food = compact.get_all_food_as_positions();
+    food.sort();
+    let mut expected = reference.board.food.clone();
+    expected.sort();
+    prop_assert_eq!(food, expected);
+    Ok(())
+}
+
+/// applies a sequence of random actions to a random position with both the compact board and
+/// the reference, comparing them after every step
+fn check_steps<B>(
+    spec: PositionSpec,
+    wrapped: bool,
+    convert: impl Fn(Game, &SnakeIDMap) -> Result<B, Box<dyn std::error::Error>>,
+) -> Result<(), TestCaseError>
+where
+    B: SimulableGame<Instruments, 4>
+        + HealthGettableGame<SnakeIDType = SnakeId>
+        + SnakeBodyGettableGame
+        + FoodGettableGame
+        + PositionGettableGame,
+{
+    let mut rng = SmallRng::seed_from_u64(spec.seed);
+    let mut reference = random_game(spec, wrapped, &mut rng);
+    let ids = build_snake_id_map(&reference);
+    let mut compact = convert(reference.clone(), &ids).unwrap();
+
+    for _ in 0..spec.n_steps {
+        if reference.board.snakes.is_empty() {
+            break;
+        }
+        let moves = reference
+            .board
+            .snakes
+            .iter()
+            .map(|s| (s.id.clone(), Move::from_index(rng.gen_range(0..4))))
+            .collect::<Vec<_>>();
+        let action = Action::<4>::collect_from(
+            moves
+                .iter()
+                .map(|(id, mv)| (ids[id], *mv))
+                .collect::<Vec<_>>()
+                .iter(),
+        );
+
+        compact = compact.apply_action(&Instruments {}, &action);
+        reference = reference_step(&reference, &moves, wrapped);
+        assert_matches_reference(&compact, &reference, &ids)?;
+    }
+    Ok(())
+}
+
+/// sequences proptest found disagreements in, kept so they are checked on every run
+#[test]
+fn test_found_disagreements() {
+    let specs = [
+        PositionSpec {
+            size: 7,
+            n_snakes: 3,
+            n_food: 4,
+            n_hazards: 9,
+            hazard_damage: 0,
+            n_steps: 2,
+            seed: 17246960592723751190,
+        },
+        PositionSpec {
+            size: 5,
+            n_snakes: 4,
+            n_food: 8,
+            n_hazards: 1,
+            hazard_damage: 0,
+            n_steps: 4,
+            seed: 18028085308740591303,
+        },
+    ];
+    for spec in specs {
+        check_steps(spec, true, WrappedCellBoard4Snakes11x11::convert_from_game).unwrap();
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn standard_matches_reference(spec in position_spec()) {
+        check_steps(spec, false, StandardCellBoard4Snakes11x11::convert_from_game)?;
+    }
+
+    #[test]
+    fn wrapped_matches_reference(spec in position_spec()) {
+        check_steps(spec, true, WrappedCellBoard4Snakes11x11::convert_from_game)?;
+    }
+}
@@ -7,7 +7,7 @@
 use core::fmt::Debug;
 
 /// Trait that all different Dimensions must implement
-pub trait Dimensions: Debug + Copy {
+pub trait Dimensions: Debug + Copy + Send + Sync {
     /// Convert from a width and a height to this dimension
     fn from_dimensions(width: u8, height: u8) -> Self;
 
@@ -1,15 +1,24 @@
 //! A compact board representation that is efficient for simulation
+//!
+//! The boards are plain `Copy` data and are always `Send + Sync`, which is why [CellNum] and
+//! [dimensions::Dimensions] require both. A board can be shared between threads for root
+//! parallel search, and with the `rayon` feature `par_simulate_with_moves` evaluates its child
+//! states in parallel.
 mod core;
 pub mod standard;
 pub mod wrapped;
 
 pub use self::core::CellIndex;
 pub use self::core::CellNum;
+pub use self::core::MAX_HAZARD_STACK;
 
 use self::dimensions::Square;
 
 pub mod dimensions;
 
+#[cfg(test)]
+mod differential;
+
 /// A cell board for a standard game (e.g. not wrapped or constrictor)
 pub type StandardCellBoard<T, D, const BOARD_SIZE: usize, const MAX_SNAKES: usize> =
     standard::CellBoard<T, D, BOARD_SIZE, MAX_SNAKES>;
@@ -23,3 +32,18 @@ pub type WrappedCellBoard<T, D, const BOARD_SIZE: usize, const MAX_SNAKES: usize
 
 /// A wrapped mode board, 11x11 with 4 snakes
 pub type WrappedCellBoard4Snakes11x11 = WrappedCellBoard<u8, Square, { 11 * 11 }, 4>;
+
+/// Compile time check that the boards and their actions are `Send + Sync`
+#[allow(dead_code)]
+fn assert_boards_are_send_and_sync<
+    T: CellNum,
+    D: dimensions::Dimensions,
+    const BOARD_SIZE: usize,
+    const MAX_SNAKES: usize,
+>() {
+    fn is_send_and_sync<X: Send + Sync>() {}
+
+    is_send_and_sync::<StandardCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>>();
+    is_send_and_sync::<WrappedCellBoard<T, D, BOARD_SIZE, MAX_SNAKES>>();
+    is_send_and_sync::<crate::types::Action<MAX_SNAKES>>();
+}
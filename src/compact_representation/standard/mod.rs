@@ -2,10 +2,11 @@
 use crate::compact_representation::core::CellNum as CN;
 use crate::impl_common_board_traits;
 use crate::types::{
-    build_snake_id_map, Action, FoodGettableGame, FoodQueryableGame, HazardQueryableGame,
-    HazardSettableGame, HeadGettableGame, HealthGettableGame, LengthGettableGame,
-    NeckQueryableGame, PositionGettableGame, RandomReasonableMovesGame, SizeDeterminableGame,
-    SnakeIDGettableGame, SnakeIDMap, SnakeId, VictorDeterminableGame, YouDeterminableGame,
+    build_snake_id_map, Action, FoodGettableGame, FoodQueryableGame, GameOutcome,
+    HazardQueryableGame, HazardSettableGame, HeadGettableGame, HealthGettableGame,
+    LengthGettableGame, NeckQueryableGame, PositionGettableGame, RandomReasonableMovesGame,
+    SizeDeterminableGame, SnakeIDGettableGame, SnakeIDMap, SnakeId, SquadQueryableGame,
+    VictorDeterminableGame, YouDeterminableGame,
 };
 /// you almost certainly want to use the `convert_from_game` method to
 /// cast from a json represention to a `CellBoard`
@@ -14,17 +15,26 @@ use crate::wire_representation::Game;
 use rand::prelude::IteratorRandom;
 use rand::Rng;
 use std::borrow::Borrow;
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Display;
 
 use crate::{
-    types::{Move, SimulableGame, SimulatorInstruments},
+    types::{
+        EliminationCause, EliminationReportingGame, Move, SimulableGame, SimulatorInstruments,
+    },
     wire_representation::Position,
 };
 
+#[cfg(feature = "rayon")]
+use super::core::par_simulate_with_moves;
 use super::core::CellBoard as CCB;
 use super::core::CellIndex;
-use super::core::{simulate_with_moves, EvaluateMode};
+use super::core::{
+    apply_action, apply_action_with_eliminations, simulate_with_moves,
+    simulate_with_moves_and_eliminations, EliminatingStates as CoreEliminatingStates, EvaluateMode,
+    SimulatedStates as CoreSimulatedStates,
+};
 use super::dimensions::{ArcadeMaze, Dimensions, Fixed, Square};
 
 /// A compact board representation that is significantly faster for simulation than
@@ -67,6 +77,18 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         Ok(CellBoard { embedded })
     }
 
+    /// for debugging, packs this board into a custom json representation
+    pub fn pack_as_hash(&self) -> HashMap<String, Vec<u32>> {
+        self.embedded.pack_as_hash()
+    }
+
+    /// for debugging, unloads a board from a custom json representation
+    pub fn from_packed_hash(hash: &HashMap<String, Vec<u32>>) -> Self {
+        Self {
+            embedded: CCB::from_packed_hash(hash),
+        }
+    }
+
     fn off_board(&self, new_head: Position) -> bool {
         new_head.x < 0
             || new_head.x >= self.embedded.get_actual_width() as i32
@@ -82,30 +104,37 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         &'a self,
         rng: &'a mut impl Rng,
     ) -> Box<dyn std::iter::Iterator<Item = (SnakeId, Move)> + 'a> {
-        let width = self.embedded.get_actual_width();
         Box::new(
             self.embedded
                 .iter_healths()
                 .enumerate()
                 .filter(|(_, health)| **health > 0)
                 .map(move |(idx, _)| {
-                    let head_pos = self.get_head_as_position(&SnakeId(idx as u8));
-
-                    let mv = IntoIterator::into_iter(Move::all())
-                        .filter(|mv| {
-                            let new_head = head_pos.add_vec(mv.to_vector());
-                            let ci = CellIndex::new(head_pos.add_vec(mv.to_vector()), width);
-
-                            !self.off_board(new_head)
-                                && !self.embedded.cell_is_body(ci)
-                                && !self.embedded.cell_is_snake_head(ci)
-                        })
+                    let mv = self
+                        .reasonable_moves_for_snake(&SnakeId(idx as u8))
+                        .into_iter()
                         .choose(rng)
                         .unwrap_or(Move::Up);
                     (SnakeId(idx as u8), mv)
                 }),
         )
     }
+
+    fn reasonable_moves_for_snake(&self, snake_id: &Self::SnakeIDType) -> Vec<Move> {
+        let width = self.embedded.get_actual_width();
+        let head_pos = self.get_head_as_position(snake_id);
+
+        IntoIterator::into_iter(Move::all())
+            .filter(|mv| {
+                let new_head = head_pos.add_vec(mv.to_vector());
+                let ci = CellIndex::new(new_head, width);
+
+                !self.off_board(new_head)
+                    && !self.embedded.cell_is_body(ci)
+                    && !self.embedded.cell_is_snake_head(ci)
+            })
+            .collect()
+    }
 }
 
 impl<
@@ -117,26 +146,120 @@ impl<
     > SimulableGame<T, MAX_SNAKES> for CellBoard<N, D, BOARD_SIZE, MAX_SNAKES>
 {
     #[allow(clippy::type_complexity)]
+    type SimulatedStates<'a>
+        = std::iter::Map<
+        CoreSimulatedStates<'a, N, D, BOARD_SIZE, MAX_SNAKES>,
+        fn((Action<MAX_SNAKES>, CCB<N, D, BOARD_SIZE, MAX_SNAKES>)) -> (Action<MAX_SNAKES>, Self),
+    >
+    where
+        Self: 'a;
+
     fn simulate_with_moves<S>(
         &self,
         instruments: &T,
         snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
-    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + '_>
+    ) -> Self::SimulatedStates<'_>
     where
         S: Borrow<[Move]>,
     {
-        Box::new(
-            simulate_with_moves(
-                &self.embedded,
-                instruments,
-                snake_ids_and_moves,
-                EvaluateMode::Standard,
-            )
-            .map(|v| {
-                let (action, board) = v;
-                (action, Self { embedded: board })
-            }),
+        simulate_with_moves(
+            &self.embedded,
+            instruments,
+            snake_ids_and_moves,
+            EvaluateMode::Standard,
+        )
+        .map(|(action, board)| (action, Self { embedded: board }))
+    }
+
+    fn apply_action(&self, instruments: &T, action: &Action<MAX_SNAKES>) -> Self {
+        Self {
+            embedded: apply_action(&self.embedded, instruments, action, EvaluateMode::Standard),
+        }
+    }
+}
+
+impl<
+        T: SimulatorInstruments,
+        D: Dimensions,
+        N: CN,
+        const BOARD_SIZE: usize,
+        const MAX_SNAKES: usize,
+    > EliminationReportingGame<T, MAX_SNAKES> for CellBoard<N, D, BOARD_SIZE, MAX_SNAKES>
+{
+    #[allow(clippy::type_complexity)]
+    type EliminatingStates<'a>
+        = std::iter::Map<
+        CoreEliminatingStates<'a, N, D, BOARD_SIZE, MAX_SNAKES>,
+        fn(
+            (
+                Action<MAX_SNAKES>,
+                CCB<N, D, BOARD_SIZE, MAX_SNAKES>,
+                [Option<EliminationCause<SnakeId>>; MAX_SNAKES],
+            ),
+        ) -> (
+            Action<MAX_SNAKES>,
+            Self,
+            [Option<EliminationCause<SnakeId>>; MAX_SNAKES],
+        ),
+    >
+    where
+        Self: 'a;
+
+    fn simulate_with_moves_and_eliminations<S>(
+        &self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Self::EliminatingStates<'_>
+    where
+        S: Borrow<[Move]>,
+    {
+        simulate_with_moves_and_eliminations(
+            &self.embedded,
+            instruments,
+            snake_ids_and_moves,
+            EvaluateMode::Standard,
+        )
+        .map(|(action, board, eliminations)| (action, Self { embedded: board }, eliminations))
+    }
+
+    fn apply_action_with_eliminations(
+        &self,
+        instruments: &T,
+        action: &Action<MAX_SNAKES>,
+    ) -> (Self, [Option<EliminationCause<SnakeId>>; MAX_SNAKES]) {
+        let (embedded, eliminations) = apply_action_with_eliminations(
+            &self.embedded,
+            instruments,
+            action,
+            EvaluateMode::Standard,
+        );
+        (Self { embedded }, eliminations)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<N: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<N, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// Same as [SimulableGame::simulate_with_moves], but the returned child states are evaluated
+    /// in parallel on the rayon thread pool. Requires the `rayon` feature.
+    pub fn par_simulate_with_moves<'a, T: SimulatorInstruments, S>(
+        &'a self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    ) -> impl rayon::iter::ParallelIterator<Item = (Action<MAX_SNAKES>, Self)> + 'a
+    where
+        S: Borrow<[Move]>,
+    {
+        use rayon::iter::ParallelIterator;
+
+        par_simulate_with_moves(
+            &self.embedded,
+            instruments,
+            snake_ids_and_moves,
+            EvaluateMode::Standard,
         )
+        .map(|(action, board)| (action, Self { embedded: board }))
     }
 }
 
@@ -146,7 +269,7 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn possible_moves<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn std::iter::Iterator<Item = (Move, CellIndex<T>)> + 'a)> {
+    ) -> Box<dyn std::iter::Iterator<Item = (Move, CellIndex<T>)> + 'a> {
         let width = self.embedded.get_actual_width();
         let head_pos = pos.into_position(width);
 
@@ -166,7 +289,7 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn neighbors<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn Iterator<Item = CellIndex<T>> + 'a)> {
+    ) -> Box<dyn Iterator<Item = CellIndex<T>> + 'a> {
         let width = self.embedded.get_actual_width();
         let head_pos = pos.into_position(width);
 
@@ -256,8 +379,10 @@ mod test {
 
     use super::*;
     use crate::{
-        compact_representation::core::Cell, game_fixture, types::build_snake_id_map,
-        wire_representation::Game as DEGame,
+        compact_representation::core::Cell,
+        game_fixture, game_with_bodies,
+        types::build_snake_id_map,
+        wire_representation::{Game as DEGame, SquadSettings},
     };
     #[derive(Debug)]
     struct Instruments;
@@ -341,6 +466,526 @@ mod test {
         assert!(compact.get_health(&SnakeId(0)) > 0);
     }
 
+    #[test]
+    fn test_simulate_produces_every_combination_once() {
+        let game_fixture = include_str!("../../../fixtures/late_stage.json");
+        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+        let g = g.expect("the json literal is valid");
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let instruments = Instruments;
+
+        let expected: usize = compact
+            .get_snake_ids()
+            .into_iter()
+            .map(|sid| {
+                compact
+                    .simulate_with_moves(&instruments, vec![(sid, Move::all())])
+                    .count()
+            })
+            .product();
+        let actions = compact
+            .simulate(&instruments, compact.get_snake_ids())
+            .map(|(action, _)| (action.own_move(), action.other_moves()))
+            .collect_vec();
+
+        assert_eq!(actions.len(), expected);
+        assert_eq!(actions.iter().unique().count(), expected);
+        assert_eq!(
+            compact
+                .simulate_with_moves(&instruments, Vec::<(SnakeId, Vec<Move>)>::new())
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_simulate_ignores_extra_snakes_and_moves() {
+        let g = game_with_bodies(
+            &[
+                &[(1, 1), (1, 2), (1, 3)],
+                &[(9, 9), (9, 8), (9, 7)],
+                &[(1, 9), (1, 8), (1, 7)],
+                &[(9, 1), (9, 2), (9, 3)],
+            ],
+            &[100, 100, 100, 100],
+            &[],
+        );
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let instruments = Instruments;
+
+        let expected = compact
+            .simulate(&instruments, compact.get_snake_ids())
+            .collect_vec();
+        let repeated_moves = Move::all()
+            .iter()
+            .chain(Move::all().iter())
+            .copied()
+            .collect_vec();
+        let mut too_many = compact
+            .get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, repeated_moves.clone()))
+            .collect_vec();
+        too_many.extend((0..2).map(|_| (SnakeId(0), vec![Move::Up])));
+
+        assert_eq!(too_many.len(), 6);
+        assert_eq!(
+            compact
+                .simulate_with_moves(&instruments, too_many)
+                .collect_vec(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_elimination_causes() {
+        let instruments = Instruments;
+        let g = game_with_bodies(
+            &[
+                &[(5, 5), (5, 4), (5, 3)],
+                &[(7, 5), (8, 5), (9, 5), (10, 5)],
+                &[(0, 0), (0, 1), (0, 2)],
+                &[(2, 8), (2, 9), (2, 10)],
+            ],
+            &[100, 100, 100, 1],
+            &[],
+        );
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let action = Action::new([
+            Some(Move::Right),
+            Some(Move::Left),
+            Some(Move::Left),
+            Some(Move::Down),
+        ]);
+        let (next, eliminations) = compact.apply_action_with_eliminations(&instruments, &action);
+        assert_eq!(
+            eliminations,
+            [
+                Some(EliminationCause::HeadToHeadCollision(SnakeId(1))),
+                None,
+                Some(EliminationCause::OutOfBounds),
+                Some(EliminationCause::Starvation),
+            ]
+        );
+        assert_eq!(next, compact.apply_action(&instruments, &action));
+        assert!(next.is_alive(&SnakeId(1)));
+
+        // every simulated child reports the same causes as applying its action
+        let children = compact
+            .simulate_with_moves_and_eliminations(
+                &instruments,
+                compact
+                    .get_snake_ids()
+                    .into_iter()
+                    .map(|sid| (sid, Move::all())),
+            )
+            .collect_vec();
+        assert_eq!(
+            children.len(),
+            compact
+                .simulate(&instruments, compact.get_snake_ids())
+                .count()
+        );
+        for (action, child, eliminations) in children {
+            assert_eq!(
+                (child, eliminations),
+                compact.apply_action_with_eliminations(&instruments, &action)
+            );
+        }
+
+        let mut g = game_with_bodies(
+            &[
+                &[(5, 5), (5, 4), (5, 3)],
+                &[(3, 3), (3, 4), (3, 5), (3, 6)],
+                &[(2, 4), (1, 4), (0, 4)],
+                &[(8, 8), (8, 9), (8, 10)],
+            ],
+            &[100, 100, 100, 5],
+            &[],
+        );
+        g.board.hazards.push(Position::new(8, 7));
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let action = Action::new([
+            Some(Move::Down),
+            Some(Move::Down),
+            Some(Move::Right),
+            Some(Move::Down),
+        ]);
+        let (_, eliminations) = compact.apply_action_with_eliminations(&instruments, &action);
+        assert_eq!(
+            eliminations,
+            [
+                Some(EliminationCause::SelfCollision),
+                None,
+                Some(EliminationCause::BodyCollision(SnakeId(1))),
+                Some(EliminationCause::HazardDamage),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_game_outcome() {
+        let instruments = Instruments;
+        let g = game_with_bodies(
+            &[&[(5, 5), (5, 4), (5, 3)], &[(7, 5), (7, 4), (7, 3)]],
+            &[100, 100],
+            &[],
+        );
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert_eq!(compact.get_outcome(), GameOutcome::Ongoing);
+
+        // equal length snakes meeting head to head both die
+        let draw = compact.apply_action(
+            &instruments,
+            &Action::new([Some(Move::Right), Some(Move::Left), None, None]),
+        );
+        assert!(draw.is_over());
+        assert_eq!(
+            draw.get_outcome(),
+            GameOutcome::Draw(vec![SnakeId(0), SnakeId(1)])
+        );
+        assert_eq!(draw.get_winner(), None);
+
+        // only the other snake is left
+        let lost = compact.apply_action(
+            &instruments,
+            &Action::new([Some(Move::Down), Some(Move::Up), None, None]),
+        );
+        assert_eq!(lost.get_outcome(), GameOutcome::Win(SnakeId(1)));
+        assert_eq!(lost.get_winner(), Some(SnakeId(1)));
+        assert_eq!(
+            CCB::from_packed_hash(&draw.embedded.pack_as_hash()),
+            draw.embedded
+        );
+
+        // who died last turn doesn't make otherwise identical boards different
+        let mut hash = draw.embedded.pack_as_hash();
+        hash.insert("eliminated_last_turn".to_string(), vec![0; 4]);
+        assert_eq!(CCB::from_packed_hash(&hash), draw.embedded);
+
+        // you are out, but two snakes are still playing
+        let g = game_with_bodies(
+            &[
+                &[(5, 5), (5, 4), (5, 3)],
+                &[(7, 5), (7, 4), (7, 3)],
+                &[(1, 5), (1, 4), (1, 3)],
+            ],
+            &[100, 100, 100],
+            &[],
+        );
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let eliminated = compact.apply_action(
+            &instruments,
+            &Action::new([Some(Move::Down), Some(Move::Up), Some(Move::Up), None]),
+        );
+        assert!(eliminated.is_over());
+        assert_eq!(eliminated.get_outcome(), GameOutcome::Eliminated);
+        assert_eq!(eliminated.get_winner(), None);
+    }
+
+    #[test]
+    fn test_packed_hash_validation() {
+        let g = game_fixture(include_str!("../../../fixtures/late_stage.json"));
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let parse = |hash: &HashMap<String, Vec<u32>>| {
+            serde_json::from_str::<CellBoard4Snakes11x11>(&serde_json::to_string(hash).unwrap())
+        };
+        assert_eq!(parse(&compact.pack_as_hash()).unwrap(), compact);
+
+        let head = compact.pack_as_hash()["heads"][0] as usize;
+        let mut off_board = compact.pack_as_hash();
+        off_board.get_mut("cells").unwrap()[head] |= 200 << 16;
+        assert!(parse(&off_board).is_err());
+
+        let mut unknown_snake = compact.pack_as_hash();
+        let cell = &mut unknown_snake.get_mut("cells").unwrap()[head];
+        *cell = (*cell & !0xff00) | (9 << 8);
+        assert!(parse(&unknown_snake).is_err());
+    }
+
+    #[test]
+    fn test_solo_game_over() {
+        let instruments = Instruments;
+        let mut g = game_with_bodies(&[&[(5, 5), (5, 4), (5, 3)]], &[2], &[]);
+        let standard: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert!(standard.is_over());
+
+        g.game.ruleset.name = "solo".to_string();
+        let solo: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert!(!solo.is_over());
+        assert_eq!(solo.get_winner(), None);
+        assert_eq!(
+            CCB::from_packed_hash(&solo.embedded.pack_as_hash()),
+            solo.embedded
+        );
+
+        let up = Action::new([Some(Move::Up), None, None, None]);
+        let next = solo.apply_action(&instruments, &up);
+        assert!(!next.is_over());
+        let starved = next.apply_action(&instruments, &up);
+        assert!(starved.is_over());
+        assert_eq!(starved.get_winner(), None);
+        assert_eq!(starved.get_outcome(), GameOutcome::SoloEnded);
+    }
+
+    #[test]
+    fn test_squad_rules() {
+        let instruments = Instruments;
+        let mut g = game_with_bodies(
+            &[
+                &[(5, 5), (5, 4), (5, 3)],
+                &[(8, 8), (8, 7), (8, 6)],
+                &[(1, 1), (1, 2), (1, 3)],
+                &[(0, 9), (0, 8), (0, 7)],
+            ],
+            &[50, 90, 100, 100],
+            &[],
+        );
+        for (snake, squad) in g.board.snakes.iter_mut().zip(["a", "a", "b", "b"]) {
+            snake.squad = Some(squad.to_string());
+        }
+        g.board.food = vec![Position::new(5, 6)];
+        g.game.ruleset.name = "squad".to_string();
+        let mut settings = game_fixture(include_str!("../../../fixtures/hazard_map_settings.json"))
+            .game
+            .ruleset
+            .settings;
+        settings.as_mut().unwrap().squad = Some(SquadSettings::default());
+        g.game.ruleset.settings = settings;
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert!(compact.is_teammate(&SnakeId(0), &SnakeId(1)));
+        assert!(!compact.is_teammate(&SnakeId(0), &SnakeId(2)));
+        assert!(!compact.is_teammate(&SnakeId(0), &SnakeId(0)));
+        assert_eq!(compact.get_squad_settings(), g.get_squad_settings());
+        assert!(!compact.is_over());
+        assert_eq!(
+            CCB::from_packed_hash(&compact.embedded.pack_as_hash()),
+            compact.embedded
+        );
+
+        let action = Action::new([
+            Some(Move::Up),
+            Some(Move::Up),
+            Some(Move::Right),
+            Some(Move::Left),
+        ]);
+        let (next, eliminations) = compact.apply_action_with_eliminations(&instruments, &action);
+        assert_eq!(
+            eliminations,
+            [
+                None,
+                None,
+                Some(EliminationCause::SquadEliminated(SnakeId(3))),
+                Some(EliminationCause::OutOfBounds),
+            ]
+        );
+        // snake 0 ate, so the whole squad is fed and grows
+        assert_eq!(next.get_health(&SnakeId(1)), 100);
+        assert_eq!(next.get_length(&SnakeId(1)), 4);
+        assert!(next.is_over());
+        assert_eq!(next.get_winner(), Some(SnakeId(0)));
+    }
+
+    #[test]
+    fn test_teammates_cross_bodies() {
+        let instruments = Instruments;
+        let mut g = game_with_bodies(
+            &[
+                &[(5, 5), (5, 4), (5, 3), (5, 2)],
+                &[(4, 4), (3, 4), (2, 4), (1, 4)],
+                &[(1, 1), (1, 2), (1, 3)],
+            ],
+            &[100, 100, 100],
+            &[],
+        );
+        for (snake, squad) in g.board.snakes.iter_mut().zip(["a", "a", "b"]) {
+            snake.squad = Some(squad.to_string());
+        }
+        g.game.ruleset.name = "squad".to_string();
+        let mut settings = game_fixture(include_str!("../../../fixtures/hazard_map_settings.json"))
+            .game
+            .ruleset
+            .settings;
+        settings.as_mut().unwrap().squad = Some(SquadSettings::default());
+        g.game.ruleset.settings = settings;
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let body = |board: &CellBoard4Snakes11x11, sid: u8| {
+            board
+                .get_snake_body_vec(&SnakeId(sid))
+                .into_iter()
+                .map(|c| c.into_position(11))
+                .map(|p| (p.x, p.y))
+                .collect_vec()
+        };
+
+        // snake 1 runs in to the body of snake 0 and keeps going while snake 0 moves away
+        let action = Action::new([Some(Move::Up), Some(Move::Right), Some(Move::Right), None]);
+        let (crossing, eliminations) =
+            compact.apply_action_with_eliminations(&instruments, &action);
+        assert_eq!(eliminations, [None; 4]);
+        assert_eq!(body(&crossing, 0), [(5, 6), (5, 5), (5, 4), (5, 3)]);
+        assert_eq!(body(&crossing, 1), [(5, 4), (4, 4), (3, 4), (2, 4)]);
+        assert!(crossing.embedded.assert_consistency());
+        assert_eq!(
+            CCB::from_packed_hash(&crossing.embedded.pack_as_hash()),
+            crossing.embedded
+        );
+
+        let next = crossing.apply_action(&instruments, &action);
+        assert_eq!(body(&next, 0), [(5, 7), (5, 6), (5, 5), (5, 4)]);
+        assert_eq!(body(&next, 1), [(6, 4), (5, 4), (4, 4), (3, 4)]);
+        let mut overlapping = g.clone();
+        for (snake, sid) in overlapping.board.snakes.iter_mut().zip([0, 1]) {
+            snake.body = body(&next, sid)
+                .into_iter()
+                .map(|(x, y)| Position::new(x, y))
+                .collect();
+            snake.head = snake.body[0];
+        }
+        overlapping.board.snakes[2].body = vec![(3, 1), (2, 1), (1, 1)]
+            .into_iter()
+            .map(|(x, y)| Position::new(x, y))
+            .collect();
+        overlapping.board.snakes[2].head = Position::new(3, 1);
+        for snake in overlapping.board.snakes.iter_mut() {
+            snake.health = 98;
+        }
+        let converted: CellBoard4Snakes11x11 = overlapping
+            .as_cell_board(&build_snake_id_map(&overlapping))
+            .unwrap();
+        assert_eq!(converted.embedded, next.embedded);
+
+        // both tails move off the shared cell, leaving it empty
+        let next = (0..3).fold(next, |board, _| board.apply_action(&instruments, &action));
+        assert_eq!(body(&next, 0), [(5, 10), (5, 9), (5, 8), (5, 7)]);
+        assert_eq!(body(&next, 1), [(9, 4), (8, 4), (7, 4), (6, 4)]);
+        assert!(next.embedded.assert_consistency());
+        assert!(!next.position_is_snake_body(CellIndex::new(Position::new(5, 4), 11)));
+
+        // without body collisions snake 1 runs in to its teammate
+        let squad = g.game.ruleset.settings.as_mut().unwrap().squad.as_mut();
+        squad.unwrap().allow_body_collisions = false;
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let (_, eliminations) = compact.apply_action_with_eliminations(&instruments, &action);
+        assert_eq!(
+            eliminations[1],
+            Some(EliminationCause::BodyCollision(SnakeId(0)))
+        );
+    }
+
+    #[test]
+    fn test_shared_length_catches_up() {
+        let instruments = Instruments;
+        let mut g = game_with_bodies(
+            &[
+                &[(5, 5), (5, 4), (5, 3), (5, 2), (5, 1)],
+                &[(8, 8), (8, 7), (8, 6)],
+                &[(1, 1), (1, 2), (1, 3)],
+            ],
+            &[100, 100, 100],
+            &[],
+        );
+        for (snake, squad) in g.board.snakes.iter_mut().zip(["a", "a", "b"]) {
+            snake.squad = Some(squad.to_string());
+        }
+        g.game.ruleset.name = "squad".to_string();
+        let mut settings = game_fixture(include_str!("../../../fixtures/hazard_map_settings.json"))
+            .game
+            .ruleset
+            .settings;
+        settings.as_mut().unwrap().squad = Some(SquadSettings {
+            allow_body_collisions: false,
+            ..SquadSettings::default()
+        });
+        g.game.ruleset.settings = settings;
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let body = |board: &CellBoard4Snakes11x11| {
+            board
+                .get_snake_body_vec(&SnakeId(1))
+                .into_iter()
+                .map(|c| c.into_position(11))
+                .map(|p| (p.x, p.y))
+                .collect_vec()
+        };
+
+        // snake 1 grows by two at once, both new segments stacked on its tail
+        let up = Action::new([Some(Move::Up), Some(Move::Up), Some(Move::Right), None]);
+        let next = compact.apply_action(&instruments, &up);
+        assert_eq!(next.get_length(&SnakeId(1)), 5);
+        assert_eq!(body(&next), [(8, 9), (8, 8), (8, 7), (8, 7), (8, 7)]);
+
+        // and the tail stays put until they are laid down
+        let next = next.apply_action(&instruments, &up);
+        assert_eq!(body(&next), [(8, 10), (8, 9), (8, 8), (8, 7), (8, 7)]);
+        let right = Action::new([Some(Move::Up), Some(Move::Right), Some(Move::Right), None]);
+        let next = next.apply_action(&instruments, &right);
+        assert_eq!(body(&next), [(9, 10), (8, 10), (8, 9), (8, 8), (8, 7)]);
+        let down = Action::new([Some(Move::Right), Some(Move::Down), Some(Move::Right), None]);
+        let next = next.apply_action(&instruments, &down);
+        assert_eq!(body(&next), [(9, 9), (9, 10), (8, 10), (8, 9), (8, 8)]);
+        assert!(next.embedded.assert_consistency());
+
+        // a tail stacked more than once converts the same way
+        let mut stacked = g.clone();
+        stacked.board.snakes[1].body = [(8, 9), (8, 8), (8, 7), (8, 7), (8, 7)]
+            .iter()
+            .map(|(x, y)| Position::new(*x, *y))
+            .collect();
+        stacked.board.snakes[1].head = Position::new(8, 9);
+        let converted: CellBoard4Snakes11x11 = stacked
+            .as_cell_board(&build_snake_id_map(&stacked))
+            .unwrap();
+        assert_eq!(body(&converted), [(8, 9), (8, 8), (8, 7), (8, 7), (8, 7)]);
+        assert_eq!(
+            CCB::from_packed_hash(&next.embedded.pack_as_hash()),
+            next.embedded
+        );
+    }
+
+    #[test]
+    fn test_apply_action_matches_simulate() {
+        let game_fixture = include_str!("../../../fixtures/late_stage.json");
+        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+        let g = g.expect("the json literal is valid");
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let instruments = Instruments;
+
+        for (action, child) in compact.simulate(&instruments, compact.get_snake_ids()) {
+            assert_eq!(compact.apply_action(&instruments, &action), child);
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_simulate_matches_simulate() {
+        use rayon::iter::ParallelIterator;
+
+        let game_fixture = include_str!("../../../fixtures/late_stage.json");
+        let g: Result<DEGame, _> = serde_json::from_slice(game_fixture.as_bytes());
+        let g = g.expect("the json literal is valid");
+        let snake_id_mapping = build_snake_id_map(&g);
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&snake_id_mapping).unwrap();
+        let instruments = Instruments;
+        let moves = compact
+            .get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, Move::all()))
+            .collect_vec();
+
+        let sequential = compact
+            .simulate_with_moves(&instruments, moves.clone())
+            .map(|(action, board)| (action.own_move(), board))
+            .collect_vec();
+        let parallel = compact
+            .par_simulate_with_moves(&instruments, moves)
+            .map(|(action, board)| (action.own_move(), board))
+            .collect::<Vec<_>>();
+
+        assert_eq!(sequential, parallel);
+    }
+
     #[test]
     fn test_set_hazard() {
         let mut c: Cell<u8> = Cell::empty();
@@ -353,6 +998,102 @@ mod test {
         assert!(!c.is_body());
     }
 
+    #[test]
+    fn test_stack_hazards() {
+        let mut c: Cell<u8> = Cell::make_body_piece(SnakeId(1), CellIndex(3));
+        c.add_hazard();
+        c.add_hazard();
+        c.set_hazard();
+        assert_eq!(c.get_hazard_count(), 2);
+        assert!(c.is_body());
+        assert_eq!(c.get_idx(), CellIndex(3));
+        assert_eq!(Cell::<u8>::from_u32(c.pack_as_u32()), c);
+
+        c.set_hazard_count(u8::MAX);
+        assert_eq!(
+            c.get_hazard_count(),
+            crate::compact_representation::MAX_HAZARD_STACK
+        );
+        c.clear_hazard();
+        assert!(!c.is_hazard());
+        assert!(c.is_body());
+    }
+
+    #[test]
+    fn test_stacked_hazard_damage() {
+        let instruments = Instruments;
+        let mut g = game_with_bodies(&[&[(5, 5), (5, 4), (5, 3)]], &[50], &[]);
+        g.board.hazards = vec![
+            Position::new(5, 6),
+            Position::new(5, 6),
+            Position::new(4, 5),
+        ];
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert_eq!(compact.get_hazard_count(&CellIndex(6 * 11 + 5)), 2);
+        assert_eq!(g.get_hazard_count(&Position::new(5, 6)), 2);
+
+        let up = compact.apply_action(
+            &instruments,
+            &Action::new([Some(Move::Up), None, None, None]),
+        );
+        assert_eq!(up.get_health(&SnakeId(0)), 50 - 1 - 2 * 15);
+        let left = compact.apply_action(
+            &instruments,
+            &Action::new([Some(Move::Left), None, None, None]),
+        );
+        assert_eq!(left.get_health(&SnakeId(0)), 50 - 1 - 15);
+    }
+
+    #[test]
+    fn test_healing_hazards() {
+        let instruments = Instruments;
+        let mut g = game_with_bodies(&[&[(5, 5), (5, 4), (5, 3)]], &[1], &[]);
+        let mut settings = game_fixture(include_str!("../../../fixtures/hazard_map_settings.json"))
+            .game
+            .ruleset
+            .settings;
+        settings.as_mut().unwrap().hazard_damage_per_turn = -20;
+        g.game.ruleset.settings = settings;
+        g.board.hazards = vec![
+            Position::new(5, 6),
+            Position::new(4, 5),
+            Position::new(4, 5),
+        ];
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        assert_eq!(compact.get_hazard_damage(), -20);
+        assert_eq!(g.get_hazard_damage(), -20);
+
+        // the pool heals the snake before it would starve
+        let up = compact.apply_action(
+            &instruments,
+            &Action::new([Some(Move::Up), None, None, None]),
+        );
+        assert_eq!(up.get_health(&SnakeId(0)), 20);
+        let left = compact.apply_action(
+            &instruments,
+            &Action::new([Some(Move::Left), None, None, None]),
+        );
+        assert_eq!(left.get_health(&SnakeId(0)), 40);
+        let (_, eliminations) = compact.apply_action_with_eliminations(
+            &instruments,
+            &Action::new([Some(Move::Right), None, None, None]),
+        );
+        assert_eq!(eliminations[0], Some(EliminationCause::Starvation));
+
+        // healing never goes above full health
+        g.board.snakes[0].health = 95;
+        let compact: CellBoard4Snakes11x11 = g.as_cell_board(&build_snake_id_map(&g)).unwrap();
+        let left = compact.apply_action(
+            &instruments,
+            &Action::new([Some(Move::Left), None, None, None]),
+        );
+        assert_eq!(left.get_health(&SnakeId(0)), 100);
+        assert_eq!(
+            CCB::from_packed_hash(&compact.embedded.pack_as_hash()),
+            compact.embedded
+        );
+    }
+
     #[test]
     fn test_clear_hazard() {
         let mut c: Cell<u8> = Cell::empty();
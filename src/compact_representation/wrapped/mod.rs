@@ -1,10 +1,11 @@
 //! A compact board representation that is efficient for simulation
 use crate::impl_common_board_traits;
 use crate::types::{
-    build_snake_id_map, FoodGettableGame, FoodQueryableGame, HazardQueryableGame,
+    build_snake_id_map, FoodGettableGame, FoodQueryableGame, GameOutcome, HazardQueryableGame,
     HazardSettableGame, HeadGettableGame, HealthGettableGame, LengthGettableGame,
     NeckQueryableGame, PositionGettableGame, RandomReasonableMovesGame, SizeDeterminableGame,
-    SnakeIDGettableGame, SnakeIDMap, SnakeId, VictorDeterminableGame, YouDeterminableGame,
+    SnakeIDGettableGame, SnakeIDMap, SnakeId, SquadQueryableGame, VictorDeterminableGame,
+    YouDeterminableGame,
 };
 
 /// you almost certainly want to use the `convert_from_game` method to
@@ -19,11 +20,20 @@ use std::error::Error;
 use std::fmt::Display;
 
 use crate::{
-    types::{Action, Move, SimulableGame, SimulatorInstruments},
+    types::{
+        Action, EliminationCause, EliminationReportingGame, Move, SimulableGame,
+        SimulatorInstruments,
+    },
     wire_representation::Position,
 };
 
-use super::core::{simulate_with_moves, EvaluateMode};
+#[cfg(feature = "rayon")]
+use super::core::par_simulate_with_moves;
+use super::core::{
+    apply_action, apply_action_with_eliminations, simulate_with_moves,
+    simulate_with_moves_and_eliminations, EliminatingStates as CoreEliminatingStates, EvaluateMode,
+    SimulatedStates as CoreSimulatedStates,
+};
 use super::core::{CellBoard as CCB, CellIndex};
 use super::dimensions::{ArcadeMaze, Dimensions, Fixed, Square};
 use super::CellNum as CN;
@@ -154,31 +164,38 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
         &'a self,
         rng: &'a mut impl Rng,
     ) -> Box<dyn std::iter::Iterator<Item = (SnakeId, Move)> + 'a> {
-        let width = self.embedded.get_actual_width();
         Box::new(
             self.embedded
                 .iter_healths()
                 .enumerate()
                 .filter(|(_, health)| **health > 0)
                 .map(move |(idx, _)| {
-                    let head = self.get_head_as_native_position(&SnakeId(idx as u8));
-                    let head_pos = head.into_position(width);
-
-                    let mv = Move::all()
-                        .iter()
-                        .filter(|mv| {
-                            let new_head = head_pos.add_vec(mv.to_vector());
-                            let ci = self.embedded.as_wrapped_cell_index(new_head);
-
-                            !self.embedded.cell_is_body(ci) && !self.embedded.cell_is_snake_head(ci)
-                        })
+                    let mv = self
+                        .reasonable_moves_for_snake(&SnakeId(idx as u8))
+                        .into_iter()
                         .choose(rng)
-                        .copied()
                         .unwrap_or(Move::Up);
                     (SnakeId(idx as u8), mv)
                 }),
         )
     }
+
+    fn reasonable_moves_for_snake(&self, snake_id: &Self::SnakeIDType) -> Vec<Move> {
+        let width = self.embedded.get_actual_width();
+        let head = self.get_head_as_native_position(snake_id);
+        let head_pos = head.into_position(width);
+
+        Move::all()
+            .iter()
+            .filter(|mv| {
+                let new_head = head_pos.add_vec(mv.to_vector());
+                let ci = self.embedded.as_wrapped_cell_index(new_head);
+
+                !self.embedded.cell_is_body(ci) && !self.embedded.cell_is_snake_head(ci)
+            })
+            .copied()
+            .collect()
+    }
 }
 
 impl<
@@ -190,26 +207,120 @@ impl<
     > SimulableGame<T, MAX_SNAKES> for CellBoard<N, D, BOARD_SIZE, MAX_SNAKES>
 {
     #[allow(clippy::type_complexity)]
+    type SimulatedStates<'a>
+        = std::iter::Map<
+        CoreSimulatedStates<'a, N, D, BOARD_SIZE, MAX_SNAKES>,
+        fn((Action<MAX_SNAKES>, CCB<N, D, BOARD_SIZE, MAX_SNAKES>)) -> (Action<MAX_SNAKES>, Self),
+    >
+    where
+        Self: 'a;
+
     fn simulate_with_moves<S>(
         &self,
         instruments: &T,
         snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
-    ) -> Box<dyn Iterator<Item = (Action<MAX_SNAKES>, Self)> + '_>
+    ) -> Self::SimulatedStates<'_>
     where
         S: Borrow<[Move]>,
     {
-        Box::new(
-            simulate_with_moves(
-                &self.embedded,
-                instruments,
-                snake_ids_and_moves,
-                EvaluateMode::Wrapped,
-            )
-            .map(|v| {
-                let (action, board) = v;
-                (action, Self { embedded: board })
-            }),
+        simulate_with_moves(
+            &self.embedded,
+            instruments,
+            snake_ids_and_moves,
+            EvaluateMode::Wrapped,
+        )
+        .map(|(action, board)| (action, Self { embedded: board }))
+    }
+
+    fn apply_action(&self, instruments: &T, action: &Action<MAX_SNAKES>) -> Self {
+        Self {
+            embedded: apply_action(&self.embedded, instruments, action, EvaluateMode::Wrapped),
+        }
+    }
+}
+
+impl<
+        T: SimulatorInstruments,
+        D: Dimensions,
+        N: CN,
+        const BOARD_SIZE: usize,
+        const MAX_SNAKES: usize,
+    > EliminationReportingGame<T, MAX_SNAKES> for CellBoard<N, D, BOARD_SIZE, MAX_SNAKES>
+{
+    #[allow(clippy::type_complexity)]
+    type EliminatingStates<'a>
+        = std::iter::Map<
+        CoreEliminatingStates<'a, N, D, BOARD_SIZE, MAX_SNAKES>,
+        fn(
+            (
+                Action<MAX_SNAKES>,
+                CCB<N, D, BOARD_SIZE, MAX_SNAKES>,
+                [Option<EliminationCause<SnakeId>>; MAX_SNAKES],
+            ),
+        ) -> (
+            Action<MAX_SNAKES>,
+            Self,
+            [Option<EliminationCause<SnakeId>>; MAX_SNAKES],
+        ),
+    >
+    where
+        Self: 'a;
+
+    fn simulate_with_moves_and_eliminations<S>(
+        &self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Self::EliminatingStates<'_>
+    where
+        S: Borrow<[Move]>,
+    {
+        simulate_with_moves_and_eliminations(
+            &self.embedded,
+            instruments,
+            snake_ids_and_moves,
+            EvaluateMode::Wrapped,
+        )
+        .map(|(action, board, eliminations)| (action, Self { embedded: board }, eliminations))
+    }
+
+    fn apply_action_with_eliminations(
+        &self,
+        instruments: &T,
+        action: &Action<MAX_SNAKES>,
+    ) -> (Self, [Option<EliminationCause<SnakeId>>; MAX_SNAKES]) {
+        let (embedded, eliminations) = apply_action_with_eliminations(
+            &self.embedded,
+            instruments,
+            action,
+            EvaluateMode::Wrapped,
+        );
+        (Self { embedded }, eliminations)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<N: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
+    CellBoard<N, D, BOARD_SIZE, MAX_SNAKES>
+{
+    /// Same as [SimulableGame::simulate_with_moves], but the returned child states are evaluated
+    /// in parallel on the rayon thread pool. Requires the `rayon` feature.
+    pub fn par_simulate_with_moves<'a, T: SimulatorInstruments, S>(
+        &'a self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+    ) -> impl rayon::iter::ParallelIterator<Item = (Action<MAX_SNAKES>, Self)> + 'a
+    where
+        S: Borrow<[Move]>,
+    {
+        use rayon::iter::ParallelIterator;
+
+        par_simulate_with_moves(
+            &self.embedded,
+            instruments,
+            snake_ids_and_moves,
+            EvaluateMode::Wrapped,
         )
+        .map(|(action, board)| (action, Self { embedded: board }))
     }
 }
 
@@ -219,7 +330,7 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn possible_moves<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn std::iter::Iterator<Item = (Move, CellIndex<T>)> + 'a)> {
+    ) -> Box<dyn std::iter::Iterator<Item = (Move, CellIndex<T>)> + 'a> {
         let width = self.embedded.get_actual_width();
         let head_pos = pos.into_position(width);
 
@@ -240,7 +351,7 @@ impl<T: CN, D: Dimensions, const BOARD_SIZE: usize, const MAX_SNAKES: usize>
     fn neighbors<'a>(
         &'a self,
         pos: &Self::NativePositionType,
-    ) -> Box<(dyn Iterator<Item = CellIndex<T>> + 'a)> {
+    ) -> Box<dyn Iterator<Item = CellIndex<T>> + 'a> {
         Box::new(self.possible_moves(pos).map(|(_, ci)| ci))
     }
 }
@@ -256,9 +367,9 @@ mod test {
         compact_representation::core::Cell,
         game_fixture,
         types::{
-            build_snake_id_map, HeadGettableGame, HealthGettableGame, Move,
-            NeighborDeterminableGame, RandomReasonableMovesGame, SimulableGame,
-            SimulatorInstruments, SnakeId,
+            build_snake_id_map, EliminationReportingGame, HeadGettableGame, HealthGettableGame,
+            Move, NeighborDeterminableGame, RandomReasonableMovesGame, SimulableGame,
+            SimulatorInstruments, SnakeIDGettableGame, SnakeId,
         },
         wire_representation::Position,
     };
@@ -302,6 +413,31 @@ mod test {
         assert_eq!(c, Cell::from_u32(as_u32));
     }
 
+    #[test]
+    fn test_apply_action_matches_simulate() {
+        let g = game_fixture(include_str!("../../../fixtures/wrapped_fixture.json"));
+        let snake_ids = build_snake_id_map(&g);
+        let compact: CellBoard4SnakesSquare11x11 = g.as_wrapped_cell_board(&snake_ids).unwrap();
+        let instruments = Instruments {};
+
+        for (action, child) in compact.simulate(&instruments, compact.get_snake_ids()) {
+            assert_eq!(compact.apply_action(&instruments, &action), child);
+        }
+
+        let moves = compact
+            .get_snake_ids()
+            .into_iter()
+            .map(|sid| (sid, Move::all()));
+        for (action, child, eliminations) in
+            compact.simulate_with_moves_and_eliminations(&instruments, moves)
+        {
+            assert_eq!(
+                compact.apply_action_with_eliminations(&instruments, &action),
+                (child, eliminations)
+            );
+        }
+    }
+
     #[test]
     fn test_wrapping_simulation_works() {
         let g = game_fixture(include_str!("../../../fixtures/wrapped_fixture.json"));
@@ -322,13 +458,13 @@ mod test {
         // the input state isn't safe to move down in, but it is if we move one to the right
         let move_map = snake_ids
             .clone()
-            .into_iter()
-            .map(|(_, sid)| (sid, [Move::Right].as_slice()))
+            .into_values()
+            .map(|sid| (sid, [Move::Right].as_slice()))
             .collect_vec();
         let instruments = Instruments {};
         let wrapped_for_down = orig_wrapped_cell
             .clone()
-            .simulate_with_moves(&instruments, move_map.into_iter())
+            .simulate_with_moves(&instruments, move_map)
             .next()
             .unwrap()
             .1;
@@ -363,7 +499,6 @@ mod test {
         for _ in 0..15 {
             let move_map = wrapped
                 .random_reasonable_move_for_each_snake(&mut rng)
-                .into_iter()
                 .map(|(sid, mv)| (sid, [mv]))
                 .collect_vec();
             wrapped = wrapped
@@ -389,10 +524,7 @@ mod test {
         let mut wrapped_cell = orig_wrapped_cell;
         let instruments = Instruments {};
         let start_health = wrapped_cell.get_health(&SnakeId(0));
-        let move_map = snake_ids
-            .into_iter()
-            .map(|(_, sid)| (sid, [mv]))
-            .collect_vec();
+        let move_map = snake_ids.into_values().map(|sid| (sid, [mv])).collect_vec();
         let start_y = wrapped_cell.get_head_as_position(&SnakeId(0)).y;
         let start_x = wrapped_cell.get_head_as_position(&SnakeId(0)).x;
         for _ in 0..rollout {
@@ -413,8 +545,8 @@ mod test {
             wrapped_cell.get_health(&SnakeId(0)) as i32,
             start_health as i32 - rollout
         );
-        assert_eq!(((start_y + (rollout * inc_y)).rem_euclid(11)) as i32, end_y);
-        assert_eq!(((start_x + (rollout * inc_x)).rem_euclid(11)) as i32, end_x);
+        assert_eq!((start_y + (rollout * inc_y)).rem_euclid(11), end_y);
+        assert_eq!((start_x + (rollout * inc_x)).rem_euclid(11), end_x);
     }
 
     #[test]
@@ -425,7 +557,7 @@ mod test {
         // we essentially "break" the snake in the cell representation when we kill it.
         let orig_crash_game = game_fixture(include_str!("../../../fixtures/wrapped_panic.json"));
         let snake_ids = build_snake_id_map(&orig_crash_game);
-        let compact_ids: Vec<SnakeId> = snake_ids.iter().map(|(_, v)| *v).collect();
+        let compact_ids: Vec<SnakeId> = snake_ids.values().copied().collect();
 
         let instruments = Instruments {};
         {
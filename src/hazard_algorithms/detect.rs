@@ -0,0 +1,438 @@
+//! Working out which hazard algorithm a game is played with. The game's settings name the map
+//! when the engine sends it, otherwise the shape of the hazards in the observed frames decides.
+//!
+//! ```no_run
+//! use battlesnake_game_types::hazard_algorithms::detect::{detect, DetectedHazards};
+//! use battlesnake_game_types::wire_representation::Game;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let frames: Vec<Game> = vec![];
+//! match detect(&frames)? {
+//!     DetectedHazards::Spiral(spiral) => println!("spiral from {:?}", spiral.hazards_at_turn(0).next()),
+//!     other => println!("{:?}", other),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::error::Error;
+
+use itertools::Itertools;
+
+use crate::wire_representation::{Game, Position};
+
+use super::{
+    expanding::{ExpandingBoxHazard, ExpandingScatterHazard, SinkholesHazard},
+    gcd,
+    maps::HazardMap,
+    pits::HazardPits,
+    royale::RoyaleHazard,
+    royale_cadence, ForwardOnlyHazardAlgorithm, NoopHazard, SpiralHazard,
+    DEFAULT_SPIRAL_HAZARD_EVERY_TURNS,
+};
+
+/// the seed and two spawns, fewer cells than this fit the start of a spiral on any map
+const MIN_SPIRAL_CELLS: usize = 3;
+
+/// A hazard algorithm found by `detect`, already observing the latest frame
+#[derive(Debug, Clone)]
+pub enum DetectedHazards {
+    /// the game has no hazards
+    None(NoopHazard),
+    /// the hazards never move
+    Static(HazardMap),
+    /// `hz_spiral`
+    Spiral(SpiralHazard),
+    /// the royale ruleset's shrinking safe area
+    Royale(RoyaleHazard),
+    /// `hz_expand_box`
+    ExpandingBox(ExpandingBoxHazard),
+    /// `hz_expand_scatter`
+    ExpandingScatter(ExpandingScatterHazard),
+    /// `sinkholes`
+    Sinkholes(SinkholesHazard),
+    /// `hz_hazard_pits`
+    Pits(HazardPits),
+}
+
+/// Identifies the hazard algorithm of a game from observed frames of it, which don't need to be
+/// consecutive or in order. A map named in `Settings::hazard_map`, the game's `map` or the
+/// royale ruleset is trusted, anything else is told apart by the shape of the hazards. The
+/// returned algorithm has observed the latest frame.
+///
+/// Randomly growing maps need to know how often they grow, which comes from the royale
+/// settings or from hazards changing between frames of consecutive turns. Inferring it from a
+/// single change can give a multiple of the real cadence
+pub fn detect<'a>(
+    frames: impl IntoIterator<Item = &'a Game>,
+) -> Result<DetectedHazards, Box<dyn Error>> {
+    let mut frames = frames.into_iter().collect::<Vec<_>>();
+    frames.sort_by_key(|g| g.turn);
+    if frames.is_empty() {
+        return Err("no frames to detect hazards from".into());
+    }
+
+    if let Some(detected) = detect_by_name(&frames)? {
+        return Ok(detected);
+    }
+    detect_by_shape(&frames)
+}
+
+fn map_name(game: &Game) -> Option<&str> {
+    game.game
+        .ruleset
+        .settings
+        .as_ref()
+        .and_then(|s| s.hazard_map.as_deref())
+        .or_else(|| (game.game.ruleset.name == "royale").then_some("royale"))
+        .or(game.game.map.as_deref())
+}
+
+/// how often the hazards grow, from the settings or from the turns the hazards changed on
+fn growth_every_n_turns(frames: &[&Game]) -> Option<u16> {
+    let last = frames.last()?;
+    royale_cadence(last).filter(|n| *n > 0).or_else(|| {
+        let every = frames
+            .iter()
+            .tuple_windows()
+            .filter(|(before, after)| {
+                after.turn - before.turn == 1 && hazard_counts(before) != hazard_counts(after)
+            })
+            .map(|(_, after)| after.turn)
+            .fold(0, gcd);
+        u16::try_from(every).ok().filter(|n| *n > 0)
+    })
+}
+
+fn require_growth(frames: &[&Game]) -> Result<u16, Box<dyn Error>> {
+    growth_every_n_turns(frames).ok_or_else(|| {
+        "can't tell how often the hazards grow, no royale settings or changes".into()
+    })
+}
+
+fn hazard_counts(game: &Game) -> HashMap<Position, usize> {
+    let mut counts = HashMap::new();
+    for p in &game.board.hazards {
+        *counts.entry(*p).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn observed<H: ForwardOnlyHazardAlgorithm<T>, T>(
+    mut hazards: H,
+    game: &Game,
+) -> Result<H, Box<dyn Error>> {
+    let _ = hazards.observe(game)?;
+    Ok(hazards)
+}
+
+fn spiral(frames: &[&Game]) -> Result<SpiralHazard, Box<dyn Error>> {
+    let every = SpiralHazard::infer_hazard_every_turns(frames.iter().copied())
+        .unwrap_or(DEFAULT_SPIRAL_HAZARD_EVERY_TURNS);
+    observed(
+        SpiralHazard::with_hazard_every_turns(every),
+        frames[frames.len() - 1],
+    )
+}
+
+/// the hole grows on turns after a multiple of its cadence, so the turns the hazards changed on
+/// don't give the cadence, the shape of the hole does
+fn sinkholes(last: &Game) -> Result<SinkholesHazard, Box<dyn Error>> {
+    let every = royale_cadence(last)
+        .filter(|n| *n > 0)
+        .or_else(|| SinkholesHazard::infer_every_n_turns(last))
+        .ok_or("hazards don't match a sinkhole growing at any rate")?;
+    observed(SinkholesHazard::with_every_n_turns(every), last)
+}
+
+/// the pits can be found from any frame where they aren't drained, the latest one is used
+fn pits(frames: &[&Game]) -> Result<HazardPits, Box<dyn Error>> {
+    let open = frames
+        .iter()
+        .rev()
+        .find(|g| !g.board.hazards.is_empty())
+        .ok_or("the pits are drained in every frame, so there's no telling where they are")?;
+    HazardPits::from_game(open)
+}
+
+/// Ok(None) for maps without a known algorithm, which are left to `detect_by_shape`
+fn detect_by_name(frames: &[&Game]) -> Result<Option<DetectedHazards>, Box<dyn Error>> {
+    let last = frames[frames.len() - 1];
+    let name = match map_name(last) {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+    let detected = match name {
+        "standard" | "empty" => DetectedHazards::None(NoopHazard()),
+        "hz_spiral" => DetectedHazards::Spiral(spiral(frames)?),
+        "royale" => DetectedHazards::Royale(observed(
+            RoyaleHazard::with_shrink_every_n_turns(require_growth(frames)?),
+            last,
+        )?),
+        "hz_expand_box" => DetectedHazards::ExpandingBox(observed(
+            ExpandingBoxHazard::with_every_n_turns(require_growth(frames)?),
+            last,
+        )?),
+        "hz_expand_scatter" => DetectedHazards::ExpandingScatter(observed(
+            ExpandingScatterHazard::with_every_n_turns(require_growth(frames)?),
+            last,
+        )?),
+        "sinkholes" => DetectedHazards::Sinkholes(sinkholes(last)?),
+        "hz_islands_bridges" => DetectedHazards::Static(HazardMap::from_game(last)),
+        "hz_hazard_pits" => DetectedHazards::Pits(pits(frames)?),
+        _ => match HazardMap::from_name(name, last.board.width, last.board.height) {
+            Ok(map) => DetectedHazards::Static(map),
+            Err(_) => return Ok(None),
+        },
+    };
+    Ok(Some(detected))
+}
+
+fn is_rectangle(cells: &HashSet<Position>) -> bool {
+    let (min_x, max_x) = match cells.iter().map(|p| p.x).minmax().into_option() {
+        Some(bounds) => bounds,
+        None => return false,
+    };
+    let (min_y, max_y) = cells.iter().map(|p| p.y).minmax().into_option().unwrap();
+    cells.len() as i32 == (max_x - min_x + 1) * (max_y - min_y + 1)
+}
+
+fn detect_by_shape(frames: &[&Game]) -> Result<DetectedHazards, Box<dyn Error>> {
+    let last = frames[frames.len() - 1];
+    if frames.iter().all(|g| g.board.hazards.is_empty()) {
+        return Ok(DetectedHazards::None(NoopHazard()));
+    }
+    let counts = hazard_counts(last);
+    if counts.values().any(|count| *count > 1) {
+        return Ok(DetectedHazards::Sinkholes(sinkholes(last)?));
+    }
+
+    let (width, height) = (last.board.width, last.board.height);
+    let hazards = counts.keys().copied().collect::<HashSet<_>>();
+    let known_layout = vec![
+        HazardMap::inner_border(width, height),
+        HazardMap::concentric_rings(width, height),
+        HazardMap::columns(width, height),
+        HazardMap::rivers_and_bridges(),
+        HazardMap::arcade_maze(),
+    ]
+    .into_iter()
+    .find(|map| {
+        map.width() == width
+            && map.height() == height
+            && map.hazards().iter().copied().collect::<HashSet<_>>() == hazards
+    });
+    if let Some(map) = known_layout {
+        return Ok(DetectedHazards::Static(map));
+    }
+    let unchanged = frames.len() > 1 && frames.iter().all(|g| hazard_counts(g) == counts);
+    if unchanged {
+        return Ok(DetectedHazards::Static(HazardMap::from_game(last)));
+    }
+
+    // the spiral and the scatter only ever add a single cell on a turn
+    let grows_one_cell_at_a_time = frames.iter().tuple_windows().all(|(before, after)| {
+        let before = hazard_counts(before);
+        let after = hazard_counts(after);
+        before.keys().all(|p| after.contains_key(p)) && after.len() <= before.len() + 1
+    });
+    // the spiral has the strictest shape, so it goes first. A cell or two fit a spiral whatever
+    // the map is, so it takes the seed and at least two spawns, and every frame has to be the
+    // spiral as it was on that frame's turn
+    if let Ok(spiral) = spiral(frames) {
+        let fits = |g: &&Game| {
+            let cells = hazard_counts(g).into_keys().collect::<HashSet<_>>();
+            spiral
+                .hazards_at_turn(g.turn as usize)
+                .filter(|p| !g.off_board(*p))
+                .collect::<HashSet<_>>()
+                == cells
+        };
+        if grows_one_cell_at_a_time
+            && spiral.is_ready_for_inc()
+            && hazards.len() >= MIN_SPIRAL_CELLS
+            && frames.iter().all(fits)
+        {
+            return Ok(DetectedHazards::Spiral(spiral));
+        }
+    }
+    // royale hazards that cover whole rows or columns along an edge are a rectangle as well, so
+    // royale goes before the box
+    let safe = (0..width as i32)
+        .flat_map(|x| (0..height as i32).map(move |y| Position::new(x, y)))
+        .filter(|p| !hazards.contains(p))
+        .collect::<HashSet<_>>();
+    if is_rectangle(&safe) {
+        return Ok(DetectedHazards::Royale(observed(
+            RoyaleHazard::with_shrink_every_n_turns(require_growth(frames)?),
+            last,
+        )?));
+    }
+    // the first cells of a scatter can be a rectangle too, so it gets its turn when they don't
+    // fit a box
+    if is_rectangle(&hazards) {
+        let growth = require_growth(frames)?;
+        if let Ok(expanding) = observed(ExpandingBoxHazard::with_every_n_turns(growth), last) {
+            return Ok(DetectedHazards::ExpandingBox(expanding));
+        }
+    }
+    if frames.len() > 1 && grows_one_cell_at_a_time {
+        return Ok(DetectedHazards::ExpandingScatter(observed(
+            ExpandingScatterHazard::with_every_n_turns(require_growth(frames)?),
+            last,
+        )?));
+    }
+    Ok(DetectedHazards::Static(HazardMap::from_game(last)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hazard_algorithms::HazardAlgorithm;
+    use crate::wire_representation::{RoyaleSettings, Settings};
+    use crate::{debug_wrapped_frame, game_fixture};
+
+    #[test]
+    fn test_detects_named_maps() {
+        let arcade = game_fixture(include_str!("../../fixtures/arcade_maze_map.json"));
+        assert!(matches!(
+            detect([&arcade]).unwrap(),
+            DetectedHazards::Static(map) if map == HazardMap::arcade_maze()
+        ));
+
+        let spiral = game_fixture(include_str!("../../fixtures/hazard_map_settings.json"));
+        match detect([&spiral]).unwrap() {
+            DetectedHazards::Spiral(detected) => {
+                assert_eq!(detected.current_turn(), spiral.turn as usize)
+            }
+            other => panic!("expected a spiral, got {:?}", other),
+        }
+
+        let mut royale = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        assert!(detect([&royale]).is_err());
+        royale.game.ruleset.settings = Some(Settings {
+            food_spawn_chance: 15,
+            minimum_food: 1,
+            hazard_damage_per_turn: 14,
+            hazard_map: None,
+            hazard_map_author: None,
+            royale: Some(RoyaleSettings {
+                shrink_every_n_turns: 20,
+            }),
+            squad: None,
+        });
+        match detect([&royale]).unwrap() {
+            DetectedHazards::Royale(detected) => assert_eq!(
+                detected.safe_area(),
+                (Position::new(3, 0), Position::new(10, 10))
+            ),
+            other => panic!("expected royale, got {:?}", other),
+        }
+
+        // the pits fill and drain during the game, they're found from the latest frame where
+        // they aren't drained
+        let mut before = royale.clone();
+        before.turn = 5;
+        before.board.hazards = vec![Position::new(2, 2), Position::new(8, 8)];
+        if let Some(settings) = before.game.ruleset.settings.as_mut() {
+            settings.hazard_map = Some("hz_hazard_pits".to_string());
+            settings.royale = Some(RoyaleSettings {
+                shrink_every_n_turns: 5,
+            });
+        }
+        let mut after = before.clone();
+        after.turn = 6;
+        after.board.hazards = before.board.hazards.repeat(2);
+        match detect([&before, &after]).unwrap() {
+            DetectedHazards::Pits(pits) => {
+                assert_eq!(pits.pits(), &before.board.hazards[..]);
+                assert_eq!(pits.hazards_at(6).count(), after.board.hazards.len());
+            }
+            other => panic!("expected hazard pits, got {:?}", other),
+        }
+        before.board.hazards = vec![];
+        after.board.hazards = vec![];
+        assert!(detect([&before, &after]).is_err());
+    }
+
+    #[test]
+    fn test_detects_by_shape() {
+        let frames = (55..=65).map(debug_wrapped_frame).collect::<Vec<_>>();
+        assert!(matches!(
+            detect(frames.iter().rev()).unwrap(),
+            DetectedHazards::Spiral(s) if s.current_turn() == frames[10].turn as usize
+        ));
+
+        let mut game = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        game.game.ruleset.name = "standard".to_string();
+        assert!(matches!(detect([&game]).unwrap(), DetectedHazards::None(_)));
+
+        game.board.hazards = HazardMap::columns(11, 11).hazards().to_vec();
+        assert!(matches!(
+            detect([&game]).unwrap(),
+            DetectedHazards::Static(map) if map == HazardMap::columns(11, 11)
+        ));
+
+        // the opening frames of a box or a scatter fit the start of a spiral as well
+        let mut empty = game.clone();
+        empty.turn = 9;
+        empty.board.hazards = vec![];
+        let mut opened = game.clone();
+        opened.turn = 10;
+        opened.board.hazards = vec![Position::new(5, 5)];
+        let mut spread = opened.clone();
+        spread.turn = 11;
+        spread.board.hazards.push(Position::new(5, 6));
+        assert!(matches!(
+            detect([&empty, &opened]).unwrap(),
+            DetectedHazards::ExpandingBox(_)
+        ));
+        assert!(matches!(
+            detect([&empty, &opened, &spread]).unwrap(),
+            DetectedHazards::ExpandingScatter(_)
+        ));
+
+        // a box that started on (5, 5) on turn 10 and grew around it on turn 20
+        let mut before = opened.clone();
+        before.turn = 19;
+        let mut after = before.clone();
+        after.turn = 20;
+        after.board.hazards = (4..=6)
+            .flat_map(|y| (4..=6).map(move |x| Position::new(x, y)))
+            .collect();
+        match detect([&empty, &opened, &before, &after]).unwrap() {
+            DetectedHazards::ExpandingBox(detected) => assert_eq!(
+                detected.hazard_area(),
+                Some((Position::new(4, 4), Position::new(6, 6)))
+            ),
+            other => panic!("expected an expanding box, got {:?}", other),
+        }
+
+        // a sinkhole that opened on turn 1 and grew on turn 3
+        before.turn = 2;
+        after.turn = 3;
+        after.board.hazards = vec![Position::new(5, 5); 2];
+        after.board.hazards.extend([
+            Position::new(4, 5),
+            Position::new(6, 5),
+            Position::new(5, 4),
+            Position::new(5, 6),
+        ]);
+        assert!(matches!(
+            detect([&before, &after]).unwrap(),
+            DetectedHazards::Sinkholes(s) if s.center() == Some(Position::new(5, 5))
+        ));
+
+        // royale shrinking from a single side, its hazards are a rectangle too
+        before.board.hazards = vec![];
+        after.board.hazards = (0..11).map(|y| Position::new(0, y)).collect();
+        match detect([&before, &after]).unwrap() {
+            DetectedHazards::Royale(detected) => assert_eq!(
+                detected.safe_area(),
+                (Position::new(1, 0), Position::new(10, 10))
+            ),
+            other => panic!("expected royale, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,719 @@
+//! The engine's growing hazard maps. Like the royale ruleset they grow every
+//! `shrink_every_n_turns` turns. The box and the scatter start on a cell picked by a per game
+//! random number generator, so they forecast a distribution over the hazards that can be added
+//! next, while the sinkhole always grows the same way.
+//!
+//! * `hz_expand_box`: the hazards start on a random cell away from the edges of the board and
+//!   grow by a ring on every side each time, so they are a square around that cell, cut off by
+//!   the edges of the board
+//! * `hz_expand_scatter`: the hazards start on a random cell away from the edges of the board and
+//!   fill the rings around it one cell at a time, in a random order within each ring
+//! * `sinkholes`: a hole opens in the middle of the board on turn 1. Every time it grows, every
+//!   cell of a square one step bigger than the last, without its corners, gets another stacked
+//!   hazard. It stops after 3 growths on 7x7 boards, 7 on 19x19 boards and 5 on the rest
+//!
+//! These follow the engine's map code as remembered rather than checked against its source or
+//! against recorded games, so `observe` errors on games that don't fit instead of forecasting
+//! from a wrong guess.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+use crate::wire_representation::{Game, Position};
+
+use super::{royale_cadence, ForwardOnlyHazardAlgorithm, HazardOutcome};
+
+/// the turn keeping shared by all of the growing maps
+#[derive(Debug, Copy, Clone)]
+struct Growth {
+    every_n_turns: Option<u16>,
+    width: i32,
+    height: i32,
+    current_turn: u16,
+    observed: bool,
+}
+
+impl Growth {
+    fn new(every_n_turns: Option<u16>) -> Self {
+        if let Some(n) = every_n_turns {
+            assert!(n > 0, "hazards have to grow every 1 or more turns");
+        }
+        Growth {
+            every_n_turns,
+            width: 0,
+            height: 0,
+            current_turn: 0,
+            observed: false,
+        }
+    }
+
+    fn observe(&mut self, game: &Game) -> Result<(), Box<dyn Error>> {
+        let every_n_turns = self
+            .every_n_turns
+            .or_else(|| royale_cadence(game))
+            .filter(|n| *n > 0)
+            .ok_or("no royale shrink_every_n_turns setting")?;
+        *self = Growth {
+            every_n_turns: Some(every_n_turns),
+            width: game.board.width as i32,
+            height: game.board.height as i32,
+            current_turn: game.turn as u16,
+            observed: true,
+        };
+        Ok(())
+    }
+
+    /// moves to the next turn, returning whether the hazards grow on it
+    fn tick(&mut self) -> bool {
+        self.current_turn += 1;
+        matches!(self.every_n_turns, Some(n) if self.current_turn % n == 0)
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Position> {
+        let (width, height) = (self.width, self.height);
+        (0..height).flat_map(move |y| (0..width).map(move |x| Position::new(x, y)))
+    }
+
+    fn on_board(&self, p: Position) -> bool {
+        p.x >= 0 && p.y >= 0 && p.x < self.width && p.y < self.height
+    }
+
+    /// the first hazard can land on any cell that isn't on the edge of the board
+    fn any_start(&self) -> Vec<HazardOutcome> {
+        let (width, height) = (self.width, self.height);
+        let starts = self
+            .cells()
+            .filter(|p| p.x > 0 && p.y > 0 && p.x < width - 1 && p.y < height - 1)
+            .collect::<Vec<_>>();
+        let probability = 1.0 / starts.len() as f64;
+        starts
+            .into_iter()
+            .map(|p| HazardOutcome {
+                probability,
+                new_hazards: vec![p],
+            })
+            .collect()
+    }
+
+    /// the cells on the board exactly `radius` steps away from `center`, diagonal steps included
+    fn ring(&self, center: Position, radius: i32) -> impl Iterator<Item = Position> {
+        self.cells().filter(move |p| distance(center, *p) == radius)
+    }
+}
+
+/// how many steps apart two cells are when diagonal steps are allowed
+fn distance(a: Position, b: Position) -> i32 {
+    (a.x - b.x).abs().max((a.y - b.y).abs())
+}
+
+fn hazard_counts(hazards: impl IntoIterator<Item = Position>) -> HashMap<Position, usize> {
+    let mut counts = HashMap::new();
+    for p in hazards {
+        *counts.entry(p).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Expanding box hazard algorithm
+#[derive(Debug, Copy, Clone)]
+pub struct ExpandingBoxHazard {
+    growth: Growth,
+    /// the cell the box started on, None before the first hazard
+    center: Option<Position>,
+    /// how many rings the box has grown around its center
+    radius: i32,
+}
+
+impl ExpandingBoxHazard {
+    /// Construct an uninitialized expanding box, which reads how often the box grows from the
+    /// royale settings of the first game it observes
+    pub fn new() -> Self {
+        ExpandingBoxHazard {
+            growth: Growth::new(None),
+            center: None,
+            radius: 0,
+        }
+    }
+
+    /// Construct an uninitialized expanding box that grows every `every_n_turns` turns. Panics
+    /// if `every_n_turns` is 0
+    pub fn with_every_n_turns(every_n_turns: u16) -> Self {
+        ExpandingBoxHazard {
+            growth: Growth::new(Some(every_n_turns)),
+            ..Self::new()
+        }
+    }
+
+    /// the bottom left and top right corners of the box, None before the first hazard
+    pub fn hazard_area(&self) -> Option<(Position, Position)> {
+        let (width, height) = (self.growth.width, self.growth.height);
+        self.center.map(|c| {
+            (
+                Position::new((c.x - self.radius).max(0), (c.y - self.radius).max(0)),
+                Position::new(
+                    (c.x + self.radius).min(width - 1),
+                    (c.y + self.radius).min(height - 1),
+                ),
+            )
+        })
+    }
+
+    /// The possible outcomes of the next growth of the box. Before the first hazard every cell
+    /// away from the edges is as likely to start the box, after that the box always grows by
+    /// the next ring, which has no new hazards once the box covers the board
+    pub fn forecast(&self) -> Vec<HazardOutcome> {
+        match self.center {
+            None => self.growth.any_start(),
+            Some(center) => vec![HazardOutcome {
+                probability: 1.0,
+                new_hazards: self.growth.ring(center, self.radius + 1).collect(),
+            }],
+        }
+    }
+
+    /// Follow one of the outcomes returned by `forecast` or `inc_turn`, growing the box
+    pub fn apply(&mut self, outcome: &HazardOutcome) {
+        match self.center {
+            Some(_) => self.radius += 1,
+            None => self.center = outcome.new_hazards.first().copied(),
+        }
+    }
+}
+
+/// where the center of a box `radius` rings big is along one side of a board `len` cells long,
+/// given the first and last cell the box covers along that side
+fn box_center(min: i32, max: i32, len: i32, radius: i32) -> Option<i32> {
+    let center = if min > 0 {
+        min + radius
+    } else if max < len - 1 {
+        max - radius
+    } else {
+        // the box covers the whole side, which grows the same way from any center that fits
+        (len - 1) / 2
+    };
+    if (center - radius).max(0) == min && (center + radius).min(len - 1) == max {
+        Some(center)
+    } else {
+        None
+    }
+}
+
+impl Default for ExpandingBoxHazard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForwardOnlyHazardAlgorithm<HazardOutcome> for ExpandingBoxHazard {
+    /// works out the box from the game's hazards, which have to be the square the box has grown
+    /// to by the game's turn, cut off by the edges of the board. Can be called on every turn.
+    /// The returned iterator is every current hazard
+    fn observe(
+        &mut self,
+        game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
+        let hazards = game.board.hazards.iter().copied().collect::<HashSet<_>>();
+        self.growth.observe(game)?;
+        let (center, radius) = if hazards.is_empty() {
+            (None, 0)
+        } else {
+            let min_x = hazards.iter().map(|p| p.x).min().unwrap();
+            let max_x = hazards.iter().map(|p| p.x).max().unwrap();
+            let min_y = hazards.iter().map(|p| p.y).min().unwrap();
+            let max_y = hazards.iter().map(|p| p.y).max().unwrap();
+            if hazards.len() as i32 != (max_x - min_x + 1) * (max_y - min_y + 1) {
+                return Err("hazards don't fill a rectangle".into());
+            }
+            // the box starts on the first turn it grows on
+            let growths = game.turn / self.growth.every_n_turns.unwrap() as i32;
+            if growths == 0 {
+                return Err("hazards before the box's first growth".into());
+            }
+            let radius = growths - 1;
+            let x = box_center(min_x, max_x, self.growth.width, radius);
+            let y = box_center(min_y, max_y, self.growth.height, radius);
+            match x.zip(y) {
+                Some((x, y)) => (Some(Position::new(x, y)), radius),
+                None => return Err(format!("hazards aren't a box grown {} times", growths).into()),
+            }
+        };
+        self.center = center;
+        self.radius = radius;
+        Ok(Box::new(hazards.into_iter()))
+    }
+
+    fn is_ready_for_inc(&self) -> bool {
+        self.growth.observed
+    }
+
+    /// On turns where the box grows this returns every possible outcome, otherwise nothing. The
+    /// box isn't changed, use `apply` to follow one of the outcomes or `observe` the next game
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = HazardOutcome>> {
+        if self.growth.tick() {
+            Box::new(self.forecast().into_iter())
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    fn current_turn(&self) -> usize {
+        self.growth.current_turn as usize
+    }
+}
+
+/// Expanding scatter hazard algorithm
+#[derive(Debug, Clone)]
+pub struct ExpandingScatterHazard {
+    growth: Growth,
+    hazards: HashSet<Position>,
+    /// every cell the hazards could have started on, the more hazards the fewer there are
+    starts: Vec<Position>,
+}
+
+impl ExpandingScatterHazard {
+    /// Construct an uninitialized expanding scatter, which reads how often hazards spread from
+    /// the royale settings of the first game it observes
+    pub fn new() -> Self {
+        ExpandingScatterHazard {
+            growth: Growth::new(None),
+            hazards: HashSet::new(),
+            starts: vec![],
+        }
+    }
+
+    /// Construct an uninitialized expanding scatter that spreads every `every_n_turns` turns.
+    /// Panics if `every_n_turns` is 0
+    pub fn with_every_n_turns(every_n_turns: u16) -> Self {
+        ExpandingScatterHazard {
+            growth: Growth::new(Some(every_n_turns)),
+            ..Self::new()
+        }
+    }
+
+    /// the cells the hazards could have started on, in board order
+    pub fn possible_starts(&self) -> &[Position] {
+        &self.starts
+    }
+
+    /// The possible outcomes of the next spread, one per free cell of the innermost ring around
+    /// any of the possible starts that isn't full yet. Each start is as likely, and so is each
+    /// free cell of its ring. Once the hazards cover the board this is a single outcome without
+    /// new hazards
+    pub fn forecast(&self) -> Vec<HazardOutcome> {
+        if self.hazards.is_empty() {
+            return self.growth.any_start();
+        }
+        let per_start = 1.0 / self.starts.len() as f64;
+        let mut chances = HashMap::new();
+        let mut full = 0.0;
+        for start in &self.starts {
+            let free = self.free_cells_of_next_ring(*start);
+            if free.is_empty() {
+                full += per_start;
+            }
+            for p in &free {
+                *chances.entry(*p).or_insert(0.0) += per_start / free.len() as f64;
+            }
+        }
+        let mut outcomes = self
+            .growth
+            .cells()
+            .filter_map(|p| {
+                chances.get(&p).map(|probability| HazardOutcome {
+                    probability: *probability,
+                    new_hazards: vec![p],
+                })
+            })
+            .collect::<Vec<_>>();
+        if full > 0.0 {
+            outcomes.push(HazardOutcome {
+                probability: full,
+                new_hazards: vec![],
+            });
+        }
+        outcomes
+    }
+
+    /// Follow one of the outcomes returned by `forecast` or `inc_turn`, dropping the starts
+    /// the new hazard couldn't have grown from
+    pub fn apply(&mut self, outcome: &HazardOutcome) {
+        for p in &outcome.new_hazards {
+            if self.growth.on_board(*p) {
+                if self.hazards.is_empty() {
+                    self.starts = vec![*p];
+                }
+                self.hazards.insert(*p);
+            }
+        }
+        let starts = std::mem::take(&mut self.starts);
+        self.starts = starts
+            .into_iter()
+            .filter(|start| self.could_start_from(*start))
+            .collect();
+    }
+
+    fn free_cells_of_next_ring(&self, start: Position) -> Vec<Position> {
+        let furthest = self.growth.width.max(self.growth.height);
+        (0..furthest)
+            .map(|radius| {
+                self.growth
+                    .ring(start, radius)
+                    .filter(|p| !self.hazards.contains(p))
+                    .collect::<Vec<_>>()
+            })
+            .find(|free| !free.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// whether the hazards are every ring around `start` up to the furthest one they reach
+    fn could_start_from(&self, start: Position) -> bool {
+        let reach = self
+            .hazards
+            .iter()
+            .map(|p| distance(start, *p))
+            .max()
+            .unwrap_or(0);
+        self.hazards.contains(&start)
+            && self
+                .growth
+                .cells()
+                .filter(|p| distance(start, *p) < reach)
+                .all(|p| self.hazards.contains(&p))
+    }
+}
+
+impl Default for ExpandingScatterHazard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForwardOnlyHazardAlgorithm<HazardOutcome> for ExpandingScatterHazard {
+    /// takes the game's hazards as the current spread and works out which cells it could have
+    /// started on. Can be called on every turn to follow the cells the engine actually picked.
+    /// The returned iterator is every current hazard
+    fn observe(
+        &mut self,
+        game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
+        self.growth.observe(game)?;
+        self.hazards = game.board.hazards.iter().copied().collect();
+        self.starts = self
+            .growth
+            .cells()
+            .filter(|p| self.could_start_from(*p))
+            .collect();
+        if !self.hazards.is_empty() && self.starts.is_empty() {
+            return Err("hazards don't fill rings around any cell".into());
+        }
+        Ok(Box::new(self.hazards.clone().into_iter()))
+    }
+
+    fn is_ready_for_inc(&self) -> bool {
+        self.growth.observed
+    }
+
+    /// On turns where the hazards spread this returns every possible outcome, otherwise
+    /// nothing. The hazards aren't changed, use `apply` to follow one of the outcomes or
+    /// `observe` the next game
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = HazardOutcome>> {
+        if self.growth.tick() {
+            Box::new(self.forecast().into_iter())
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    fn current_turn(&self) -> usize {
+        self.growth.current_turn as usize
+    }
+}
+
+/// Sinkholes hazard algorithm
+#[derive(Debug, Copy, Clone)]
+pub struct SinkholesHazard {
+    growth: Growth,
+}
+
+impl SinkholesHazard {
+    /// Construct an uninitialized sinkhole, which reads how often the hole grows from the
+    /// royale settings of the first game it observes
+    pub fn new() -> Self {
+        SinkholesHazard {
+            growth: Growth::new(None),
+        }
+    }
+
+    /// Construct an uninitialized sinkhole that grows every `every_n_turns` turns. Panics if
+    /// `every_n_turns` is 0
+    pub fn with_every_n_turns(every_n_turns: u16) -> Self {
+        SinkholesHazard {
+            growth: Growth::new(Some(every_n_turns)),
+        }
+    }
+
+    /// Work out how often the hole grows from a game with a sinkhole, for games without royale
+    /// settings. None if the hazards don't match a sinkhole growing at any rate
+    pub fn infer_every_n_turns(game: &Game) -> Option<u16> {
+        (1..=game.turn.max(1) as u16).find(|n| {
+            let mut sinkhole = SinkholesHazard::with_every_n_turns(*n);
+            sinkhole.observe(game).is_ok()
+        })
+    }
+
+    /// the center of the hole, None before it opens
+    pub fn center(&self) -> Option<Position> {
+        if self.growth.observed && self.growth.current_turn > 0 {
+            Some(Position::new(self.growth.width / 2, self.growth.height / 2))
+        } else {
+            None
+        }
+    }
+
+    /// The hazards the next turn stacks on the board. The hole always grows the same way, so
+    /// this is a single outcome on turns it grows on and nothing on the rest
+    pub fn forecast(&self) -> Vec<HazardOutcome> {
+        self.outcomes_on(self.growth.current_turn + 1)
+    }
+
+    fn max_growths(&self) -> u16 {
+        match self.growth.width {
+            7 => 3,
+            19 => 7,
+            _ => 5,
+        }
+    }
+
+    /// the hazards the hole adds on `turn`
+    fn growth_on(&self, turn: u16) -> Vec<Position> {
+        let n = match self.growth.every_n_turns {
+            Some(n) if turn > 0 && (turn - 1) % n == 0 => n,
+            _ => return vec![],
+        };
+        let center = Position::new(self.growth.width / 2, self.growth.height / 2);
+        let growths = ((turn - 1) / n) as i32;
+        if turn == 1 {
+            vec![center]
+        } else if growths <= self.max_growths() as i32 {
+            self.growth
+                .cells()
+                .filter(|p| {
+                    let (dx, dy) = ((p.x - center.x).abs(), (p.y - center.y).abs());
+                    distance(center, *p) <= growths && !(dx == growths && dy == growths)
+                })
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    fn outcomes_on(&self, turn: u16) -> Vec<HazardOutcome> {
+        let new_hazards = self.growth_on(turn);
+        if new_hazards.is_empty() {
+            vec![]
+        } else {
+            vec![HazardOutcome {
+                probability: 1.0,
+                new_hazards,
+            }]
+        }
+    }
+
+    /// every hazard on the board at `turn`, with stacked cells repeated
+    fn hazards_at(&self, turn: u16) -> Vec<Position> {
+        (1..=turn).flat_map(|t| self.growth_on(t)).collect()
+    }
+}
+
+impl Default for SinkholesHazard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForwardOnlyHazardAlgorithm<HazardOutcome> for SinkholesHazard {
+    /// checks that the game's hazards are the hole as it is on the game's turn. The returned
+    /// iterator is every current hazard, with stacked cells repeated
+    fn observe(
+        &mut self,
+        game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
+        self.growth.observe(game)?;
+        let hazards = self.hazards_at(self.growth.current_turn);
+        if hazard_counts(hazards.iter().copied())
+            != hazard_counts(game.board.hazards.iter().copied())
+        {
+            return Err(format!("hazards aren't a sinkhole on turn {}", game.turn).into());
+        }
+        Ok(Box::new(hazards.into_iter()))
+    }
+
+    fn is_ready_for_inc(&self) -> bool {
+        self.growth.observed
+    }
+
+    /// On turns where the hole grows this returns its growth as a single outcome, otherwise
+    /// nothing
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = HazardOutcome>> {
+        self.growth.tick();
+        Box::new(self.outcomes_on(self.growth.current_turn).into_iter())
+    }
+
+    fn current_turn(&self) -> usize {
+        self.growth.current_turn as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_fixture;
+
+    fn game_with_hazards(turn: i32, hazards: Vec<Position>) -> Game {
+        let mut game = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        game.turn = turn;
+        game.board.hazards = hazards;
+        game
+    }
+
+    fn total(outcomes: &[HazardOutcome]) -> f64 {
+        outcomes.iter().map(|o| o.probability).sum()
+    }
+
+    fn square(min: Position, max: Position) -> Vec<Position> {
+        (min.y..=max.y)
+            .flat_map(|y| (min.x..=max.x).map(move |x| Position::new(x, y)))
+            .collect()
+    }
+
+    #[test]
+    fn test_expanding_box() {
+        let mut expanding = ExpandingBoxHazard::with_every_n_turns(5);
+        let hazards = square(Position::new(4, 4), Position::new(6, 6));
+        assert_eq!(
+            expanding
+                .observe(&game_with_hazards(10, hazards))
+                .unwrap()
+                .count(),
+            9
+        );
+        for _ in 11..15 {
+            assert_eq!(expanding.inc_turn().count(), 0);
+        }
+        let outcomes = expanding.inc_turn().collect::<Vec<_>>();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].probability, 1.0);
+        assert_eq!(outcomes[0].new_hazards.len(), 5 * 5 - 3 * 3);
+        expanding.apply(&outcomes[0]);
+        assert_eq!(
+            expanding.hazard_area(),
+            Some((Position::new(3, 3), Position::new(7, 7)))
+        );
+
+        // a box that started next to a corner, cut off by the edges
+        let corner = square(Position::new(0, 0), Position::new(3, 3));
+        let _ = expanding
+            .observe(&game_with_hazards(15, corner.clone()))
+            .unwrap();
+        assert_eq!(
+            expanding.hazard_area(),
+            Some((Position::new(0, 0), Position::new(3, 3)))
+        );
+        assert_eq!(expanding.forecast()[0].new_hazards.len(), 5 * 5 - 4 * 4);
+
+        assert!(expanding
+            .observe(&game_with_hazards(10, corner.clone()))
+            .is_err());
+        assert!(expanding.observe(&game_with_hazards(3, corner)).is_err());
+        assert!(expanding
+            .observe(&game_with_hazards(
+                10,
+                vec![Position::new(0, 0), Position::new(2, 2)]
+            ))
+            .is_err());
+
+        let mut empty = ExpandingBoxHazard::with_every_n_turns(5);
+        let _ = empty.observe(&game_with_hazards(0, vec![])).unwrap();
+        assert_eq!(empty.forecast().len(), 9 * 9);
+        assert!((total(&empty.forecast()) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_expanding_scatter() {
+        let mut scatter = ExpandingScatterHazard::with_every_n_turns(1);
+        let game = game_with_hazards(3, vec![Position::new(5, 5), Position::new(5, 6)]);
+        assert_eq!(scatter.observe(&game).unwrap().count(), 2);
+        assert_eq!(
+            scatter.possible_starts(),
+            &[Position::new(5, 5), Position::new(5, 6)]
+        );
+        let outcomes = scatter.inc_turn().collect::<Vec<_>>();
+        // the first rings around both starts, which share 4 cells
+        assert_eq!(outcomes.len(), 7 + 7 - 4);
+        assert!((total(&outcomes) - 1.0).abs() < 1e-9);
+        let corner = outcomes
+            .iter()
+            .find(|o| o.new_hazards == vec![Position::new(4, 4)])
+            .unwrap();
+        assert!((corner.probability - 0.5 / 7.0).abs() < 1e-9);
+
+        scatter.apply(corner);
+        assert_eq!(scatter.possible_starts(), &[Position::new(5, 5)]);
+        let outcomes = scatter.forecast();
+        assert_eq!(outcomes.len(), 6);
+        assert!((total(&outcomes) - 1.0).abs() < 1e-9);
+        assert_eq!(scatter.current_turn(), 4);
+
+        let spread_out = game_with_hazards(3, vec![Position::new(0, 0), Position::new(5, 5)]);
+        assert!(scatter.observe(&spread_out).is_err());
+    }
+
+    #[test]
+    fn test_sinkholes() {
+        let mut sinkhole = SinkholesHazard::with_every_n_turns(2);
+        let _ = sinkhole.observe(&game_with_hazards(0, vec![])).unwrap();
+        assert_eq!(sinkhole.center(), None);
+        let opened = sinkhole.inc_turn().collect::<Vec<_>>();
+        assert_eq!(
+            opened,
+            vec![HazardOutcome {
+                probability: 1.0,
+                new_hazards: vec![Position::new(5, 5)],
+            }]
+        );
+        assert_eq!(sinkhole.center(), Some(Position::new(5, 5)));
+
+        assert_eq!(sinkhole.inc_turn().count(), 0);
+        let grown = sinkhole.forecast();
+        assert_eq!(grown.len(), 1);
+        // a 3x3 square without its corners
+        assert_eq!(grown[0].new_hazards.len(), 5);
+        assert_eq!(sinkhole.inc_turn().collect::<Vec<_>>(), grown);
+
+        let mut game = game_with_hazards(3, vec![Position::new(5, 5)]);
+        game.board
+            .hazards
+            .extend(grown[0].new_hazards.iter().copied());
+        let mut observed = SinkholesHazard::with_every_n_turns(2);
+        assert_eq!(observed.observe(&game).unwrap().count(), 6);
+        assert_eq!(observed.forecast(), sinkhole.forecast());
+        assert_eq!(SinkholesHazard::infer_every_n_turns(&game), Some(2));
+        game.board.hazards.pop();
+        assert!(observed.observe(&game).is_err());
+
+        // the hole stops growing after 5 growths on 11x11 boards
+        let deepest = sinkhole
+            .hazards_at(100)
+            .into_iter()
+            .filter(|p| *p == Position::new(5, 5))
+            .count();
+        assert_eq!(deepest, 1 + 5);
+        let mut small = game_with_hazards(0, vec![]);
+        small.board.width = 7;
+        small.board.height = 7;
+        let mut sinkhole = SinkholesHazard::with_every_n_turns(1);
+        let _ = sinkhole.observe(&small).unwrap();
+        let growths = (0..20).filter(|_| sinkhole.inc_turn().count() > 0).count();
+        assert_eq!(growths, 1 + 3);
+    }
+}
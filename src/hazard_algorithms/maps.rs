@@ -0,0 +1,350 @@
+//! Layouts of the engine's static hazard maps. These maps place all of their hazards on turn 0
+//! and never move them, so the whole layout is known before the first move. The game names its
+//! map in `Settings::hazard_map`, or for newer games in `NestedGame::map`, and `HazardMap::for_game`
+//! turns that string into the layout.
+//!
+//! Layouts that only exist for a single board size are written down as ascii grids, in the same
+//! format `Game::from_ascii` reads: the top row comes first and `x` is a hazard.
+//!
+//! Not every static map is built in yet: `hz_islands_bridges` and the 19x19 and 25x25 layouts of
+//! `hz_rivers_bridges` still have to be captured from a game with `HazardMap::from_game`.
+//! `hz_hazard_pits` fills and drains its pits during the game, so it isn't a static map at all,
+//! see `pits::HazardPits`.
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use crate::{
+    types::{HazardQueryableGame, HazardSettableGame, SizeDeterminableGame},
+    wire_representation::{Game, Position},
+};
+
+use super::{HazardAlgorithm, HazardDiff};
+
+/// how far from the edge of the board the inner border and the outermost ring are drawn
+const INNER_BORDER_OFFSET: i32 = 2;
+
+const RIVERS_AND_BRIDGES_11X11: &str = "
+    . . . . . x . . . . .
+    . . . . . x . . . . .
+    . . . . . . . . . . .
+    . . . . . x . . . . .
+    . . . . . x . . . . .
+    x x . x x x x x . x x
+    . . . . . x . . . . .
+    . . . . . x . . . . .
+    . . . . . . . . . . .
+    . . . . . x . . . . .
+    . . . . . x . . . . .
+";
+
+const ARCADE_MAZE_19X21: &str = "
+    x . x x x x x x x x x x x x x x x . x
+    x . . . . . . . . x . . . . . . . . x
+    x . x x . x x x . x . x x x . x x . x
+    x . . . . . . . . . . . . . . . . . x
+    x . x x . x . x x x x x . x . x x . x
+    x . . . . x . . . x . . . x . . . . x
+    x . . x . x x x . x . x x x . x . . x
+    x . . x . x . . . . . . . x . x . . x
+    x x x x . x . x . x . x . x . x x x x
+    . . . . . . . x . . . x . . . . . . .
+    x x x x . x . x . x . x . x . x x x x
+    x . . x . x . . . . . . . x . x . . x
+    x . . x . x . x x x x x . x . x . . x
+    x . . . . . . . . x . . . . . . . . x
+    x . x x . x x x . x . x x x . x x . x
+    x . . x . . . . . . . . . . . x . . x
+    x x . x . x . x x x x x . x . x . x x
+    x . . . . x . . . x . . . x . . . . x
+    x . x x x x x x . x . x x x x x x . x
+    x . . . . . . . . . . . . . . . . . x
+    x . x x x x x x x x x x x x x x x . x
+";
+
+/// The fixed hazards of a static map on a board of a given size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HazardMap {
+    width: u32,
+    height: u32,
+    hazards: Vec<Position>,
+}
+
+impl HazardMap {
+    fn from_cells(width: u32, height: u32, is_hazard: impl Fn(i32, i32) -> bool) -> Self {
+        let hazards = (0..height as i32)
+            .flat_map(|y| (0..width as i32).map(move |x| Position::new(x, y)))
+            .filter(|p| is_hazard(p.x, p.y))
+            .collect();
+        HazardMap {
+            width,
+            height,
+            hazards,
+        }
+    }
+
+    /// `hz_inner_wall`: a one cell wide square of hazards two cells in from the edge of the board
+    pub fn inner_border(width: u32, height: u32) -> Self {
+        let (w, h) = (width as i32, height as i32);
+        Self::from_cells(width, height, |x, y| {
+            let inside =
+                |v: i32, size: i32| v >= INNER_BORDER_OFFSET && v < size - INNER_BORDER_OFFSET;
+            inside(x, w)
+                && inside(y, h)
+                && (x == INNER_BORDER_OFFSET
+                    || x == w - INNER_BORDER_OFFSET - 1
+                    || y == INNER_BORDER_OFFSET
+                    || y == h - INNER_BORDER_OFFSET - 1)
+        })
+    }
+
+    /// `hz_rings`: the inner border repeated every other cell towards the center of the board
+    pub fn concentric_rings(width: u32, height: u32) -> Self {
+        let (w, h) = (width as i32, height as i32);
+        Self::from_cells(width, height, |x, y| {
+            // distance to the nearest edge, rings sit on the even distances from 2 up
+            let depth = x.min(y).min(w - 1 - x).min(h - 1 - y);
+            depth >= INNER_BORDER_OFFSET && depth % 2 == 0 && depth < w.min(h) / 2
+        })
+    }
+
+    /// `hz_columns`: a hazard on every cell with odd x and odd y
+    pub fn columns(width: u32, height: u32) -> Self {
+        Self::from_cells(width, height, |x, y| x % 2 == 1 && y % 2 == 1)
+    }
+
+    /// `hz_rivers_bridges` on an 11x11 board: a river down each middle line of the board, crossed
+    /// by a bridge in each quarter
+    pub fn rivers_and_bridges() -> Self {
+        // the layout is a constant, so it always parses
+        Self::from_ascii(RIVERS_AND_BRIDGES_11X11).unwrap()
+    }
+
+    /// `arcade_maze` on its 19x21 board: the walls of the maze
+    pub fn arcade_maze() -> Self {
+        // the layout is a constant, so it always parses
+        Self::from_ascii(ARCADE_MAZE_19X21).unwrap()
+    }
+
+    /// Reads a layout from an ascii grid with the top row first, where `x` is a hazard and every
+    /// other token is a safe cell
+    pub fn from_ascii(s: &str) -> Result<Self, Box<dyn Error>> {
+        let rows = s
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .filter(|row| !row.is_empty())
+            .collect::<Vec<_>>();
+        let width = rows
+            .first()
+            .map(|row| row.len())
+            .ok_or("empty hazard map")?;
+        if rows.iter().any(|row| row.len() != width) {
+            return Err("hazard map rows have different lengths".into());
+        }
+        let height = rows.len();
+        let hazards = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row_idx, row)| {
+                let y = (height - 1 - row_idx) as i32;
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, token)| **token == "x")
+                    .map(move |(x, _)| Position::new(x as i32, y))
+            })
+            .collect();
+        Ok(HazardMap {
+            width: width as u32,
+            height: height as u32,
+            hazards,
+        })
+    }
+
+    /// Captures the hazards of an observed game as a layout. Static maps that aren't built in,
+    /// like `hz_islands_bridges`, can be read from their turn 0 frame this way. Stacked hazards
+    /// are only kept once
+    pub fn from_game(game: &Game) -> Self {
+        let mut seen = HashSet::new();
+        HazardMap {
+            width: game.board.width,
+            height: game.board.height,
+            hazards: game
+                .board
+                .hazards
+                .iter()
+                .copied()
+                .filter(|p| seen.insert(*p))
+                .collect(),
+        }
+    }
+
+    /// Looks a static map up by the name the engine gives it, for a board of the given size.
+    /// Errors for the maps and sizes that aren't built in, see the module docs
+    pub fn from_name(name: &str, width: u32, height: u32) -> Result<Self, Box<dyn Error>> {
+        let map = match name {
+            "hz_inner_wall" => Self::inner_border(width, height),
+            "hz_rings" => Self::concentric_rings(width, height),
+            "hz_columns" => Self::columns(width, height),
+            "hz_rivers_bridges" if (width, height) == (11, 11) => Self::rivers_and_bridges(),
+            "arcade_maze" => Self::arcade_maze(),
+            "hz_rivers_bridges" | "hz_islands_bridges" => {
+                return Err(format!(
+                    "no built in {}x{} layout for {}, capture it with HazardMap::from_game",
+                    width, height, name
+                )
+                .into())
+            }
+            "hz_hazard_pits" => {
+                return Err("hz_hazard_pits isn't a static hazard map, see HazardPits".into())
+            }
+            _ => return Err(format!("no built in layout for hazard map {}", name).into()),
+        };
+        if map.width != width || map.height != height {
+            return Err(format!(
+                "hazard map {} is {}x{}, not {}x{}",
+                name, map.width, map.height, width, height
+            )
+            .into());
+        }
+        Ok(map)
+    }
+
+    /// The layout of the map the game is played on, looked up by the name in its settings or
+    /// its `map` field
+    pub fn for_game(game: &Game) -> Result<Self, Box<dyn Error>> {
+        let name = game
+            .game
+            .ruleset
+            .settings
+            .as_ref()
+            .and_then(|s| s.hazard_map.as_deref())
+            .or(game.game.map.as_deref())
+            .ok_or("the game doesn't name its hazard map")?;
+        Self::from_name(name, game.board.width, game.board.height)
+    }
+
+    /// the width of the board this layout is for
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// the height of the board this layout is for
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// every hazard of the layout, each cell once
+    pub fn hazards(&self) -> &[Position] {
+        &self.hazards
+    }
+
+    /// Makes every cell of the layout a hazard in the given game. Cells that already are
+    /// hazards are left alone, and no hazards are removed
+    pub fn apply<G>(&self, game: &mut G) -> Result<(), Box<dyn Error>>
+    where
+        G: HazardSettableGame + HazardQueryableGame + SizeDeterminableGame,
+    {
+        if game.get_width() != self.width || game.get_height() != self.height {
+            return Err(format!(
+                "hazard map is {}x{} but the board is {}x{}",
+                self.width,
+                self.height,
+                game.get_width(),
+                game.get_height()
+            )
+            .into());
+        }
+        for position in &self.hazards {
+            let native = game.native_from_position(*position);
+            if !game.is_hazard(&native) {
+                game.set_hazard(native);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// the layout is the same on every turn
+impl HazardAlgorithm for HazardMap {
+    fn hazards_at(&self, _turn: usize) -> Box<dyn Iterator<Item = Position> + '_> {
+        Box::new(self.hazards.iter().copied())
+    }
+
+    fn diff(&self, _from_turn: usize, _to_turn: usize) -> HazardDiff {
+        HazardDiff::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::StandardCellBoard4Snakes11x11;
+    use crate::game_fixture;
+    use crate::types::{build_snake_id_map, PositionGettableGame};
+
+    #[test]
+    fn test_parametric_layouts() {
+        let border = HazardMap::inner_border(11, 11);
+        assert_eq!(border.hazards().len(), 6 * 4);
+        assert!(border.hazards().contains(&Position::new(2, 2)));
+        assert!(border.hazards().contains(&Position::new(8, 5)));
+        assert!(!border.hazards().contains(&Position::new(5, 5)));
+
+        let rings = HazardMap::concentric_rings(11, 11);
+        assert_eq!(rings.hazards().len(), 6 * 4 + 2 * 4);
+        assert!(rings.hazards().contains(&Position::new(4, 4)));
+        assert!(!rings.hazards().contains(&Position::new(3, 3)));
+        assert!(!rings.hazards().contains(&Position::new(5, 5)));
+
+        assert_eq!(HazardMap::columns(11, 11).hazards().len(), 25);
+        assert_eq!(HazardMap::rivers_and_bridges().hazards().len(), 17);
+    }
+
+    #[test]
+    fn test_arcade_maze_matches_fixture() {
+        let game = game_fixture(include_str!("../../fixtures/arcade_maze_map.json"));
+        let map = HazardMap::for_game(&game).unwrap();
+        assert_eq!(map, HazardMap::arcade_maze());
+        assert_eq!(
+            map.hazards().iter().collect::<HashSet<_>>(),
+            game.board.hazards.iter().collect::<HashSet<_>>()
+        );
+        assert_eq!(HazardMap::from_game(&game).hazards().len(), 187);
+        assert!(HazardMap::from_name("arcade_maze", 11, 11).is_err());
+        assert!(HazardMap::from_name("hz_made_up", 11, 11).is_err());
+        assert_eq!(
+            HazardMap::from_name("hz_rivers_bridges", 11, 11).unwrap(),
+            HazardMap::rivers_and_bridges()
+        );
+        for (name, size) in [
+            ("hz_rivers_bridges", 19),
+            ("hz_islands_bridges", 11),
+            ("hz_hazard_pits", 11),
+        ] {
+            assert!(HazardMap::from_name(name, size, size).is_err());
+        }
+    }
+
+    #[test]
+    fn test_applies_to_any_board() {
+        let mut game = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        game.board.hazards = vec![Position::new(2, 2)];
+        let map = HazardMap::inner_border(11, 11);
+        let ids = build_snake_id_map(&game);
+        let mut compact =
+            StandardCellBoard4Snakes11x11::convert_from_game(game.clone(), &ids).unwrap();
+
+        map.apply(&mut game).unwrap();
+        assert_eq!(game.board.hazards.len(), map.hazards().len());
+        map.apply(&mut compact).unwrap();
+        for position in map.hazards() {
+            assert_eq!(
+                compact.get_hazard_count(&compact.native_from_position(*position)),
+                1
+            );
+        }
+        assert!(HazardMap::arcade_maze().apply(&mut game).is_err());
+        assert_eq!(map.hazards_at(500).count(), map.hazards().len());
+        assert_eq!(map.diff(0, 500), HazardDiff::default());
+    }
+}
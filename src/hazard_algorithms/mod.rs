@@ -1,34 +1,139 @@
 //! traits and structs for simulating hazard algorithms in battlesnake
 //! implements non-deterministic forecasting for randomized algorithms
 
+use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::error::Error;
 
-use crate::{wire_representation::{Game, Position}, types::Move};
+use itertools::Itertools;
+
+use crate::{
+    types::{HazardSettableGame, Move, SizeDeterminableGame, Vector},
+    wire_representation::{Game, Position},
+};
+
+pub mod detect;
+pub mod expanding;
+pub mod maps;
+pub mod pits;
+pub mod royale;
+pub mod simulation;
 
 /// Represents a hazard algorithms that can only be wound forward (interface permits one turn at a time)
 pub trait ForwardOnlyHazardAlgorithm<T>: Clone + std::fmt::Debug {
     /// use this to initialize the hazard algorithm. See implementation
     /// specific notes for how to use for each hazard algorithm. The returned
     /// iterator is the list of positions observed on the first creation of hazards
-    fn observe(&mut self, game: &Game) -> Result<Box <dyn Iterator<Item=Position>>, Box<dyn Error>>;
+    fn observe(
+        &mut self,
+        game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>>;
 
     /// determines if this forward only hazard algorithm is ready for inc calls
     fn is_ready_for_inc(&self) -> bool;
 
     /// Wind the turn forward by one. Returned iterator represents the new hazards
     /// that were created on the wound turn.
-    fn inc_turn(&mut self) -> Box<dyn Iterator<Item=T>>;
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = T>>;
 
     /// get the current turn of this hazard algorithm
     fn current_turn(&self) -> usize;
 }
 
+/// Represents a hazard algorithm whose hazards can be queried at any turn, so a search can move
+/// back and forth between turns without cloning and winding the algorithm for every node
+pub trait HazardAlgorithm: std::fmt::Debug {
+    /// every hazard on the board at the given turn, stacked hazards repeated. Like `inc_turn`
+    /// this can include cells that are off the board
+    fn hazards_at(&self, turn: usize) -> Box<dyn Iterator<Item = Position> + '_>;
+
+    /// The hazards that appear and disappear going from `from_turn` to `to_turn`, which can
+    /// be earlier than `from_turn` to rewind
+    fn diff(&self, from_turn: usize, to_turn: usize) -> HazardDiff {
+        let mut counts = std::collections::BTreeMap::<Position, i64>::new();
+        for position in self.hazards_at(to_turn) {
+            *counts.entry(position).or_default() += 1;
+        }
+        for position in self.hazards_at(from_turn) {
+            *counts.entry(position).or_default() -= 1;
+        }
+        let mut diff = HazardDiff::default();
+        for (position, change) in counts {
+            let changed = if change > 0 {
+                &mut diff.added
+            } else {
+                &mut diff.removed
+            };
+            changed.extend(std::iter::repeat(position).take(change.unsigned_abs() as usize));
+        }
+        diff
+    }
+}
+
+/// The change in hazards between two turns, see `HazardAlgorithm::diff`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HazardDiff {
+    /// the hazards that appear, stacked hazards repeated
+    pub added: Vec<Position>,
+    /// the hazards that disappear, stacked hazards repeated
+    pub removed: Vec<Position>,
+}
+
+impl HazardDiff {
+    /// the diff that undoes this one
+    pub fn reversed(&self) -> Self {
+        HazardDiff {
+            added: self.removed.clone(),
+            removed: self.added.clone(),
+        }
+    }
+
+    /// Applies the change to a game, skipping cells off the board. Every removed hazard takes one
+    /// hazard off its cell's stack, games that can't stack hazards clear the whole cell
+    pub fn apply<G: HazardSettableGame + SizeDeterminableGame>(&self, game: &mut G) {
+        let width = game.get_width() as i32;
+        let height = game.get_height() as i32;
+        let on_board = |p: &&Position| p.x >= 0 && p.y >= 0 && p.x < width && p.y < height;
+        for position in self.removed.iter().filter(on_board) {
+            let native = game.native_from_position(*position);
+            game.remove_hazard(native);
+        }
+        for position in self.added.iter().filter(on_board) {
+            let native = game.native_from_position(*position);
+            game.add_hazard(native);
+        }
+    }
+}
+
+/// One of the possible sets of hazards a randomized hazard algorithm can add on a turn
+#[derive(Debug, Clone, PartialEq)]
+pub struct HazardOutcome {
+    /// how likely this outcome is, the probabilities of all outcomes of a turn sum to 1
+    pub probability: f64,
+    /// the cells that get another hazard
+    pub new_hazards: Vec<Position>,
+}
+
+/// how often hazards grow according to the game's royale settings, which the engine's randomly
+/// growing maps share with the royale ruleset
+fn royale_cadence(game: &Game) -> Option<u16> {
+    game.game
+        .ruleset
+        .settings
+        .as_ref()
+        .and_then(|s| s.royale)
+        .and_then(|royale| u16::try_from(royale.shrink_every_n_turns).ok())
+}
+
 #[derive(Clone, Copy, Debug)]
 /// Hazard algorithm that does not do anything
 pub struct NoopHazard();
 
 impl ForwardOnlyHazardAlgorithm<Position> for NoopHazard {
-    fn observe(&mut self, _game: &Game) -> Result<Box <dyn Iterator<Item=Position>>, Box<dyn Error>> {
+    fn observe(
+        &mut self,
+        _game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
         Ok(Box::new(std::iter::empty()))
     }
 
@@ -36,7 +141,7 @@ impl ForwardOnlyHazardAlgorithm<Position> for NoopHazard {
         false
     }
 
-    fn inc_turn(&mut self) -> Box<dyn Iterator<Item=Position>> {
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = Position>> {
         Box::new(std::iter::empty())
     }
 
@@ -45,13 +150,16 @@ impl ForwardOnlyHazardAlgorithm<Position> for NoopHazard {
     }
 }
 
+/// how often the engine's spiral map spawns a hazard, the payload doesn't say so games with a
+/// different cadence need to configure it or infer it from observed frames
+const DEFAULT_SPIRAL_HAZARD_EVERY_TURNS: u8 = 3;
 
 /// Spiral hazard algorithm
 #[derive(Debug, Copy, Clone)]
 pub struct SpiralHazard {
     hazard_every_turns: u8,
     seed_cell: Position,
-    first_turn_seen: u16,
+    first_turn_seen: Option<u16>,
     current_turn: u16,
     next_hazard_cell: Position,
     direction: Move,
@@ -60,15 +168,182 @@ pub struct SpiralHazard {
 impl SpiralHazard {
     /// Construct an unitialized spiral hazard algorithm
     pub fn new() -> Self {
+        Self::with_hazard_every_turns(DEFAULT_SPIRAL_HAZARD_EVERY_TURNS)
+    }
+
+    /// Construct an unitialized spiral hazard algorithm that spawns a hazard every
+    /// `hazard_every_turns` turns instead of the default of 3. Panics if `hazard_every_turns` is 0
+    pub fn with_hazard_every_turns(hazard_every_turns: u8) -> Self {
+        assert!(
+            hazard_every_turns > 0,
+            "hazards have to spawn every 1 or more turns"
+        );
         SpiralHazard {
-            hazard_every_turns: 0,
+            hazard_every_turns,
             seed_cell: Position { x: 0, y: 0 },
-            first_turn_seen: 0,
+            first_turn_seen: None,
             current_turn: 0,
             next_hazard_cell: Position { x: 0, y: 0 },
             direction: Move::Up,
         }
     }
+
+    /// how many turns there are between two hazards spawning
+    pub fn hazard_every_turns(&self) -> u8 {
+        self.hazard_every_turns
+    }
+
+    /// Infers how often hazards spawn from a sequence of observed games, which don't need to be
+    /// consecutive. Returns None until at least two spawns after the seed have been seen
+    pub fn infer_hazard_every_turns<'a>(games: impl IntoIterator<Item = &'a Game>) -> Option<u8> {
+        let mut counts = games
+            .into_iter()
+            .map(|g| (g.turn, g.board.hazards.len()))
+            .collect::<Vec<_>>();
+        counts.sort_unstable();
+        // a spawn seen between consecutive turns pins its turn down exactly, and the cadence
+        // divides the gaps between those. The seed isn't necessarily placed on the cadence
+        let every = counts
+            .iter()
+            .tuple_windows()
+            .filter(|((turn_before, before), (turn, after))| {
+                turn - turn_before == 1 && *before > 0 && *after == before + 1
+            })
+            .map(|(_, (turn, _))| *turn)
+            .tuple_windows()
+            .map(|(a, b)| b - a)
+            .fold(0, gcd);
+        if every == 0 {
+            return None;
+        }
+        u8::try_from(every).ok()
+    }
+
+    /// a spiral whose seed cell appeared on the given turn
+    fn seeded(&self, seed_cell: Position, turn: u16) -> Self {
+        SpiralHazard {
+            hazard_every_turns: self.hazard_every_turns,
+            seed_cell,
+            first_turn_seen: Some(turn),
+            current_turn: turn,
+            next_hazard_cell: seed_cell.add_vec(Move::Up.to_vector()),
+            direction: Move::Right,
+        }
+    }
+
+    /// Initializes the spiral from any turn of a game, by finding the seed cell the current
+    /// hazards spiral out of. Cells of the spiral that fall off the board are allowed to be
+    /// missing from the game. Uses the default cadence, see `recover_from_game` for others
+    pub fn from_game(game: &Game) -> Result<Self, Box<dyn Error>> {
+        Self::new().recover_from_game(game)
+    }
+
+    /// Like `from_game`, but keeps the cadence this algorithm was constructed with
+    pub fn recover_from_game(&self, game: &Game) -> Result<Self, Box<dyn Error>> {
+        let hazards = game.board.hazards.iter().copied().collect::<HashSet<_>>();
+        if hazards.is_empty() {
+            return Err("didn't observe spiral seed".into());
+        }
+        let turn = game.turn as u16;
+        let every = self.hazard_every_turns as u16;
+        // the most spawns there can have been by this turn, with the seed on turn 0
+        let max_spawns = turn / every;
+        let spirals = hazards
+            .iter()
+            .sorted()
+            .filter_map(|seed| {
+                let mut on_board = 0;
+                let mut spawns = 0;
+                for k in 0.. {
+                    let position = seed.add_vec(spiral_offset(k));
+                    if on_board == hazards.len() {
+                        // spawns off the board count too, including the ones after the last
+                        // hazard on the board, as long as the turn leaves room for them
+                        if game.off_board(position) && k <= max_spawns {
+                            spawns = k;
+                            continue;
+                        }
+                        break;
+                    }
+                    if game.off_board(position) {
+                        continue;
+                    }
+                    if !hazards.contains(&position) {
+                        return None;
+                    }
+                    on_board += 1;
+                    spawns = k;
+                }
+                Some((*seed, spawns))
+            })
+            .collect::<Vec<_>>();
+        if spirals.is_empty() {
+            return Err("hazards don't form a spiral".into());
+        }
+        // spirals seeded next to the edge of the board can fit other seeds as well, but those
+        // can take more spawns than there have been turns
+        let (seed, spawns) = spirals
+            .into_iter()
+            .find(|(_, spawns)| *spawns <= max_spawns)
+            .ok_or("too many hazards for the current turn")?;
+
+        let first_turn_seen = if spawns == 0 {
+            turn
+        } else {
+            (max_spawns - spawns) * every
+        };
+        let mut hazard = self.seeded(seed, first_turn_seen);
+        while hazard.current_turn < turn {
+            let _ = hazard.inc_turn();
+        }
+        Ok(hazard)
+    }
+
+    /// All the hazards on the board at the given turn, the seed cell first and then every cell
+    /// in the order the spiral spawned them. This includes cells that are off the board. Returns
+    /// nothing if the seed cell hasn't been observed yet
+    pub fn hazards_at_turn(&self, turn: usize) -> impl Iterator<Item = Position> {
+        let seed = self.seed_cell;
+        let every = self.hazard_every_turns as usize;
+        let spawned = match self.first_turn_seen {
+            Some(first) if turn >= first as usize => Some(turn / every - first as usize / every),
+            _ => None,
+        };
+        spawned
+            .into_iter()
+            .flat_map(move |n| (0..=n as u16).map(move |k| seed.add_vec(spiral_offset(k))))
+    }
+}
+
+impl HazardAlgorithm for NoopHazard {
+    fn hazards_at(&self, _turn: usize) -> Box<dyn Iterator<Item = Position> + '_> {
+        Box::new(std::iter::empty())
+    }
+}
+
+impl HazardAlgorithm for SpiralHazard {
+    /// every cell the spiral has spawned by the given turn, see `hazards_at_turn`
+    fn hazards_at(&self, turn: usize) -> Box<dyn Iterator<Item = Position> + '_> {
+        Box::new(self.hazards_at_turn(turn))
+    }
+
+    /// the spiral only ever grows, so the diff is the tail of the later turn's hazards
+    fn diff(&self, from_turn: usize, to_turn: usize) -> HazardDiff {
+        let earlier = self.hazards_at_turn(from_turn.min(to_turn)).count();
+        let changed = self
+            .hazards_at_turn(from_turn.max(to_turn))
+            .skip(earlier)
+            .collect();
+        let diff = HazardDiff {
+            added: changed,
+            removed: vec![],
+        };
+        if to_turn >= from_turn {
+            diff
+        } else {
+            diff.reversed()
+        }
+    }
 }
 
 impl Default for SpiralHazard {
@@ -84,7 +359,7 @@ impl Default for SpiralHazard {
 // x x x
 // x x x
 // but crucially not:
-// x x 
+// x x
 // x x
 fn next_perfect_odd_square(n: u16) -> u16 {
     // 1 -> 1
@@ -108,6 +383,35 @@ fn next_perfect_odd_square(n: u16) -> u16 {
     next_base * next_base
 }
 
+/// the offset from the seed cell of the k-th cell the spiral spawns, the seed cell being the
+/// 0th. Each ring starts just right of the top left corner and runs clockwise
+fn spiral_offset(k: u16) -> Vector {
+    if k == 0 {
+        return Vector { x: 0, y: 0 };
+    }
+    let radius = ((next_perfect_odd_square(k) as f32).sqrt() as i64 - 1) / 2;
+    // how far into the current ring this cell is
+    let j = k as i64 - (2 * radius - 1) * (2 * radius - 1);
+    let (x, y) = if j < 2 * radius {
+        (j - (radius - 1), radius)
+    } else if j < 4 * radius {
+        (radius, radius - (j - (2 * radius - 1)))
+    } else if j < 6 * radius {
+        (radius - (j - (4 * radius - 1)), -radius)
+    } else {
+        (-radius, -radius + (j - (6 * radius - 1)))
+    };
+    Vector { x, y }
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
 fn is_perfect_odd_square(n: u16) -> bool {
     let sqrt = (n as f32).sqrt().floor() as u16;
     sqrt * sqrt == n && sqrt % 2 == 1
@@ -117,59 +421,70 @@ impl ForwardOnlyHazardAlgorithm<Position> for SpiralHazard {
     /// call this with game states until the seed cell has been observed
     /// which will usually be on turn 3, once you've seen the seed cell
     /// you should stop calling observe, and start calling inc_turn to
-    /// calculate forward hazard squares
-    fn observe(&mut self, game: &Game) -> Result<Box <dyn Iterator<Item=Position>>, Box<dyn Error>> {
+    /// calculate forward hazard squares. If the first game observed is
+    /// already past the seed turn the spiral is recovered from the current
+    /// hazards, and all of them are returned
+    fn observe(
+        &mut self,
+        game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
         if self.is_ready_for_inc() {
             return Err("already ready for inc".into());
         }
-        if self.first_turn_seen == 0 {
-            if game.board.hazards.len() > 1 {
-                return Err("didn't observe spiral seed".into());
-            } else if !game.board.hazards.is_empty() {
-                let hazard_pos = game.board.hazards[0];
-                self.seed_cell = hazard_pos;
-
-                // TODO: no way to detect this from the payload right now
-                self.hazard_every_turns = 3;
-
-                self.first_turn_seen = game.turn as u16;
-                self.current_turn = game.turn as u16;
-
-                self.next_hazard_cell = self.seed_cell.add_vec(Move::Up.to_vector());
-                self.direction = Move::Right;
-                return Ok(Box::new(Some(self.seed_cell).into_iter()))
-            }
+        if game.board.hazards.len() > 1 {
+            // we've missed the seed being placed, so work out where the spiral started
+            *self = self.recover_from_game(game)?;
+            let hazards = self
+                .hazards_at_turn(self.current_turn())
+                .filter(|p| !game.off_board(*p))
+                .collect::<Vec<_>>();
+            return Ok(Box::new(hazards.into_iter()));
+        } else if !game.board.hazards.is_empty() {
+            *self = self.seeded(game.board.hazards[0], game.turn as u16);
+            return Ok(Box::new(Some(self.seed_cell).into_iter()));
         }
         Ok(Box::new(None.into_iter()))
     }
 
     fn is_ready_for_inc(&self) -> bool {
-        self.first_turn_seen != 0
+        self.first_turn_seen.is_some()
     }
 
     fn current_turn(&self) -> usize {
         self.current_turn as usize
     }
 
-    fn inc_turn(&mut self) -> Box<dyn Iterator<Item=Position>> {
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = Position>> {
         self.current_turn += 1;
         if self.current_turn % self.hazard_every_turns as u16 == 0 {
-            let turns_elapsed = self.current_turn - self.first_turn_seen;
+            let turns_elapsed = self.current_turn - self.first_turn_seen.unwrap_or_default();
             // plus 1 because the seed cell
             let spawns_elapsed = (turns_elapsed / self.hazard_every_turns as u16) + 1;
             let next_square = next_perfect_odd_square(spawns_elapsed);
-            let radius = ((next_square as f32).sqrt()/2.0).floor() as u16;
+            let radius = ((next_square as f32).sqrt() / 2.0).floor() as u16;
             let result = self.next_hazard_cell;
             self.next_hazard_cell = self.next_hazard_cell.add_vec(self.direction.to_vector());
 
-            if self.next_hazard_cell.x - self.seed_cell.x == radius as i32 && self.next_hazard_cell.y - self.seed_cell.y == radius as i32 {
+            if self.next_hazard_cell.x - self.seed_cell.x == radius as i32
+                && self.next_hazard_cell.y - self.seed_cell.y == radius as i32
+            {
                 self.direction = Move::Down;
-            } else if self.next_hazard_cell.x - self.seed_cell.x == radius as i32 && self.next_hazard_cell.y - self.seed_cell.y == -(radius as i32) {
+            } else if self.next_hazard_cell.x - self.seed_cell.x == radius as i32
+                && self.next_hazard_cell.y - self.seed_cell.y == -(radius as i32)
+            {
                 self.direction = Move::Left;
-            } else if self.next_hazard_cell.x - self.seed_cell.x == -(radius as i32) && self.next_hazard_cell.y - self.seed_cell.y == -(radius as i32) {
+            } else if self.next_hazard_cell.x - self.seed_cell.x == -(radius as i32)
+                && self.next_hazard_cell.y - self.seed_cell.y == -(radius as i32)
+            {
                 self.direction = Move::Up;
-            } else if self.next_hazard_cell.x - self.seed_cell.x == -(radius as i32) && self.next_hazard_cell.y - self.seed_cell.y == radius as i32 {
-                debug_assert!(is_perfect_odd_square(spawns_elapsed+1), "spawns_elapsed: {}", spawns_elapsed);
+            } else if self.next_hazard_cell.x - self.seed_cell.x == -(radius as i32)
+                && self.next_hazard_cell.y - self.seed_cell.y == radius as i32
+            {
+                debug_assert!(
+                    is_perfect_odd_square(spawns_elapsed + 1),
+                    "spawns_elapsed: {}",
+                    spawns_elapsed
+                );
                 self.direction = Move::Up;
             }
             if is_perfect_odd_square(spawns_elapsed) {
@@ -185,11 +500,16 @@ impl ForwardOnlyHazardAlgorithm<Position> for SpiralHazard {
 
 #[cfg(test)]
 mod tests {
-    use std::{fs, collections::HashSet, iter::FromIterator, path};
+    use std::{collections::HashSet, iter::FromIterator};
 
-    use crate::{wire_representation::{Position, Game}, types::Move};
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11,
+        debug_wrapped_frame,
+        types::{build_snake_id_map, HazardQueryableGame, Move, PositionGettableGame},
+        wire_representation::Position,
+    };
 
-    use super::{SpiralHazard, ForwardOnlyHazardAlgorithm};
+    use super::{ForwardOnlyHazardAlgorithm, HazardAlgorithm, HazardDiff, SpiralHazard};
 
     #[test]
     fn test_next_perfect_square() {
@@ -212,7 +532,7 @@ mod tests {
         let mut s = SpiralHazard {
             hazard_every_turns: 3,
             seed_cell: Position { x: 0, y: 0 },
-            first_turn_seen: 3,
+            first_turn_seen: Some(3),
             current_turn: 3,
             next_hazard_cell: Position { x: 0, y: 1 },
             direction: Move::Right,
@@ -301,24 +621,221 @@ mod tests {
     fn test_matches_frames_from_game() {
         let mut maintained_hazards = HashSet::new();
         let mut hazard_alg = SpiralHazard::new();
-        let self_file = path::Path::new(env!("CARGO_MANIFEST_DIR"));
-        eprintln!("{:?}", self_file);
-        for i in 1..=193 {
-            let file_name = self_file.join(format!("fixtures/debug_wrapped/debug_game_{}.json", i));
-            let file_bytes = fs::read(file_name).unwrap();
-            let game: Game = serde_json::from_slice(&file_bytes).unwrap();
-
+        for game in (1..=193).map(debug_wrapped_frame) {
             if !hazard_alg.is_ready_for_inc() {
                 let iter = hazard_alg.observe(&game).unwrap();
                 maintained_hazards.extend(iter);
             } else {
                 let new_hazards = hazard_alg.inc_turn();
                 maintained_hazards.extend(new_hazards);
-                let hazards_set = HashSet::from_iter(game.board.hazards.into_iter());
+                let hazards_set = HashSet::from_iter(game.board.hazards);
                 assert!(hazard_alg.current_turn == game.turn as u16);
                 assert!(hazards_set == maintained_hazards);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hazards_at_turn_matches_inc_turn() {
+        let mut s = SpiralHazard {
+            hazard_every_turns: 3,
+            seed_cell: Position { x: 5, y: 5 },
+            first_turn_seen: Some(3),
+            current_turn: 3,
+            next_hazard_cell: Position { x: 5, y: 6 },
+            direction: Move::Right,
+        };
+        assert_eq!(s.hazards_at_turn(2).count(), 0);
+        let mut maintained = vec![Position { x: 5, y: 5 }];
+        for turn in 4..400 {
+            maintained.extend(s.inc_turn());
+            assert_eq!(s.hazards_at_turn(turn).collect::<Vec<_>>(), maintained);
+        }
+    }
+
+    #[test]
+    fn test_spiral_diff_rewinds() {
+        let s = SpiralHazard {
+            hazard_every_turns: 3,
+            seed_cell: Position { x: 5, y: 5 },
+            first_turn_seen: Some(3),
+            current_turn: 3,
+            next_hazard_cell: Position { x: 5, y: 6 },
+            direction: Move::Right,
+        };
+        let forward = s.diff(10, 30);
+        assert!(forward.removed.is_empty());
+        assert_eq!(forward.added.len(), 10 - 3);
+        assert_eq!(s.diff(30, 10), forward.reversed());
+        assert_eq!(s.diff(0, 4).added, vec![Position { x: 5, y: 5 }]);
+
+        let mut hazards = s.hazards_at(10).collect::<Vec<_>>();
+        hazards.extend(forward.added);
+        assert_eq!(hazards, s.hazards_at(30).collect::<Vec<_>>());
+    }
 
+    /// a stack of two hazards that moves between two cells every turn
+    #[derive(Debug)]
+    struct Blinking;
+
+    impl HazardAlgorithm for Blinking {
+        fn hazards_at(&self, turn: usize) -> Box<dyn Iterator<Item = Position> + '_> {
+            let x = (turn % 2) as i32;
+            Box::new(std::iter::repeat(Position { x, y: 0 }).take(2))
+        }
+    }
+
+    #[test]
+    fn test_default_diff_counts_stacks() {
+        let diff = Blinking.diff(0, 1);
+        assert_eq!(diff.added, vec![Position { x: 1, y: 0 }; 2]);
+        assert_eq!(diff.removed, vec![Position { x: 0, y: 0 }; 2]);
+        assert_eq!(Blinking.diff(0, 2), HazardDiff::default());
+
+        let mut game = crate::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        game.board.hazards = Blinking.hazards_at(0).collect();
+        diff.apply(&mut game);
+        assert_eq!(
+            game.board.hazards,
+            Blinking.hazards_at(1).collect::<Vec<_>>()
+        );
+        diff.reversed().apply(&mut game);
+        assert_eq!(
+            game.board.hazards,
+            Blinking.hazards_at(0).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_diff_removes_one_hazard_of_a_stack() {
+        let stacked = Position { x: 3, y: 3 };
+        let diff = HazardDiff {
+            added: vec![],
+            removed: vec![stacked],
+        };
+        let mut game = crate::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        game.board.hazards = vec![stacked, stacked];
+        let ids = build_snake_id_map(&game);
+        let mut compact =
+            StandardCellBoard4Snakes11x11::convert_from_game(game.clone(), &ids).unwrap();
+        assert_eq!(
+            compact.get_hazard_count(&compact.native_from_position(stacked)),
+            2
+        );
+
+        diff.apply(&mut game);
+        assert_eq!(game.board.hazards, vec![stacked]);
+        diff.apply(&mut compact);
+        assert_eq!(
+            compact.get_hazard_count(&compact.native_from_position(stacked)),
+            1
+        );
+    }
+
+    #[test]
+    fn test_observes_mid_game() {
+        let games = (1..=193).map(debug_wrapped_frame).collect::<Vec<_>>();
+        for start in [10, 11, 12, 60, 150] {
+            let mut hazard_alg = SpiralHazard::new();
+            let observed = hazard_alg.observe(&games[start]).unwrap();
+            let mut maintained_hazards = HashSet::<Position>::from_iter(observed);
+            assert!(hazard_alg.is_ready_for_inc());
+            assert_eq!(hazard_alg.current_turn(), games[start].turn as usize);
+            for game in &games[start + 1..] {
+                maintained_hazards.extend(hazard_alg.inc_turn());
+                let hazards_set = HashSet::from_iter(game.board.hazards.iter().copied());
+                assert_eq!(hazards_set, maintained_hazards);
+                assert_eq!(
+                    HashSet::from_iter(hazard_alg.hazards_at_turn(game.turn as usize)),
+                    hazards_set
+                );
             }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_custom_cadence() {
+        let every = 5;
+        let seed = SpiralHazard {
+            hazard_every_turns: every,
+            seed_cell: Position { x: 5, y: 5 },
+            first_turn_seen: Some(2),
+            current_turn: 2,
+            next_hazard_cell: Position { x: 5, y: 6 },
+            direction: Move::Right,
+        };
+        let mut template = crate::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        template.board.hazards.clear();
+        let games = (0..80)
+            .map(|turn| {
+                let mut game = template.clone();
+                game.turn = turn;
+                game.board.hazards = seed
+                    .hazards_at_turn(turn as usize)
+                    .filter(|p| !game.off_board(*p))
+                    .collect();
+                game
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(SpiralHazard::infer_hazard_every_turns(&games[..8]), None);
+        assert_eq!(SpiralHazard::infer_hazard_every_turns(&games), Some(every));
+        assert_eq!(
+            SpiralHazard::infer_hazard_every_turns(&games[..16]),
+            Some(every)
+        );
+
+        let hazard_alg = SpiralHazard::with_hazard_every_turns(every)
+            .recover_from_game(&games[33])
+            .unwrap();
+        assert_eq!(hazard_alg.hazard_every_turns(), every);
+        for game in &games[33..] {
+            let forecast = hazard_alg
+                .hazards_at_turn(game.turn as usize)
+                .filter(|p| !game.off_board(*p))
+                .collect::<HashSet<_>>();
+            assert_eq!(
+                forecast,
+                HashSet::from_iter(game.board.hazards.iter().copied())
+            );
+        }
+    }
+
+    #[test]
+    fn test_observes_mid_game_off_the_edge() {
+        let seed = SpiralHazard {
+            hazard_every_turns: 3,
+            seed_cell: Position { x: 1, y: 5 },
+            first_turn_seen: Some(3),
+            current_turn: 3,
+            next_hazard_cell: Position { x: 1, y: 6 },
+            direction: Move::Right,
+        };
+        let mut template = crate::game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        template.board.hazards.clear();
+        for turn in 6..150 {
+            let mut game = template.clone();
+            game.turn = turn;
+            game.board.hazards = seed
+                .hazards_at_turn(turn as usize)
+                .filter(|p| !game.off_board(*p))
+                .collect();
+
+            let mut hazard_alg = SpiralHazard::new();
+            let observed = hazard_alg.observe(&game).unwrap().collect::<Vec<_>>();
+            assert!(observed.iter().all(|p| !game.off_board(*p)));
+            assert_eq!(
+                HashSet::<Position>::from_iter(observed),
+                HashSet::from_iter(game.board.hazards.iter().copied())
+            );
+            // spawns off the board are counted, so the seed is never placed later than it was
+            assert!(hazard_alg.first_turn_seen.unwrap() <= 3, "turn {}", turn);
+        }
+    }
+
+    #[test]
+    fn test_infers_cadence_from_game() {
+        let games = (1..=193).map(debug_wrapped_frame).collect::<Vec<_>>();
+        assert_eq!(SpiralHazard::infer_hazard_every_turns(&games), Some(3));
+    }
+}
@@ -0,0 +1,141 @@
+//! The engine's `hz_hazard_pits` map. Its hazards sit in fixed pits that fill up and drain again
+//! over and over: the pits open on turn 1, get another stacked hazard every
+//! `shrink_every_n_turns` turns until they are 4 deep, and are then drained for
+//! `shrink_every_n_turns` turns before the next cycle fills them again.
+//!
+//! The cycle follows the engine's map code as remembered rather than checked against its source
+//! or against recorded games. Where the pits are isn't built in, it is captured from a game
+//! while they are open, see `HazardPits::from_game`.
+
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::wire_representation::{Game, Position};
+
+use super::{royale_cadence, HazardAlgorithm};
+
+/// the first turn the pits hold hazards on
+const START_TURN: usize = 1;
+
+/// how many hazards the pits stack up before they drain
+const MAX_DEPTH: usize = 4;
+
+/// Hazard pits that fill and drain on a fixed cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HazardPits {
+    pits: Vec<Position>,
+    every_n_turns: u16,
+}
+
+impl HazardPits {
+    /// Construct pits on the given cells that get another hazard every `every_n_turns` turns.
+    /// Panics if `every_n_turns` is 0
+    pub fn new(pits: Vec<Position>, every_n_turns: u16) -> Self {
+        assert!(every_n_turns > 0, "pits have to fill every 1 or more turns");
+        let mut pits = pits;
+        pits.sort_by_key(|p| (p.y, p.x));
+        pits.dedup();
+        HazardPits {
+            pits,
+            every_n_turns,
+        }
+    }
+
+    /// Captures the pits from a game on a turn they aren't drained, reading how often they fill
+    /// from the game's royale settings. Errors if the hazards aren't all as deep as the cycle
+    /// has them on the game's turn
+    pub fn from_game(game: &Game) -> Result<Self, Box<dyn Error>> {
+        let every_n_turns = royale_cadence(game)
+            .filter(|n| *n > 0)
+            .ok_or("no royale shrink_every_n_turns setting")?;
+        let mut depths = HashMap::new();
+        for p in &game.board.hazards {
+            *depths.entry(*p).or_insert(0) += 1;
+        }
+        if depths.is_empty() {
+            return Err("the pits are drained, so there's no telling where they are".into());
+        }
+        let pits = HazardPits::new(depths.keys().copied().collect(), every_n_turns);
+        let depth = pits.depth_at(game.turn as usize);
+        if depths.values().any(|d| *d != depth) {
+            return Err(format!("hazards aren't pits {} deep on turn {}", depth, game.turn).into());
+        }
+        Ok(pits)
+    }
+
+    /// the cells of the pits, in board order
+    pub fn pits(&self) -> &[Position] {
+        &self.pits
+    }
+
+    /// how many hazards are stacked in every pit on the given turn
+    pub fn depth_at(&self, turn: usize) -> usize {
+        if turn < START_TURN {
+            return 0;
+        }
+        ((turn - START_TURN) / self.every_n_turns as usize + 1) % (MAX_DEPTH + 1)
+    }
+}
+
+impl HazardAlgorithm for HazardPits {
+    /// every pit, repeated as deep as it is on the given turn
+    fn hazards_at(&self, turn: usize) -> Box<dyn Iterator<Item = Position> + '_> {
+        let depth = self.depth_at(turn);
+        Box::new(
+            self.pits
+                .iter()
+                .flat_map(move |p| std::iter::repeat(*p).take(depth)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_fixture;
+    use crate::wire_representation::{RoyaleSettings, Settings};
+
+    #[test]
+    fn test_pits_fill_and_drain() {
+        let pits = HazardPits::new(vec![Position::new(8, 8), Position::new(2, 2)], 5);
+        assert_eq!(pits.pits(), &[Position::new(2, 2), Position::new(8, 8)]);
+        let depths = [0, 1, 5, 6, 16, 20, 21, 25, 26]
+            .iter()
+            .map(|turn| pits.depth_at(*turn))
+            .collect::<Vec<_>>();
+        assert_eq!(depths, vec![0, 1, 1, 2, 4, 4, 0, 0, 1]);
+        assert_eq!(pits.hazards_at(16).count(), 2 * 4);
+        let drained = pits.diff(20, 21);
+        assert_eq!(drained.added, vec![]);
+        assert_eq!(drained.removed.len(), 2 * 4);
+        assert_eq!(pits.diff(21, 26).added, pits.pits().to_vec());
+    }
+
+    #[test]
+    fn test_pits_from_game() {
+        let mut game = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        game.turn = 11;
+        game.board.hazards = [Position::new(2, 2), Position::new(8, 8)].repeat(3);
+        assert!(HazardPits::from_game(&game).is_err());
+
+        game.game.ruleset.settings = Some(Settings {
+            food_spawn_chance: 15,
+            minimum_food: 1,
+            hazard_damage_per_turn: 14,
+            hazard_map: Some("hz_hazard_pits".to_string()),
+            hazard_map_author: None,
+            royale: Some(RoyaleSettings {
+                shrink_every_n_turns: 5,
+            }),
+            squad: None,
+        });
+        let pits = HazardPits::from_game(&game).unwrap();
+        assert_eq!(pits, HazardPits::new(game.board.hazards.clone(), 5));
+
+        game.turn = 16;
+        assert!(HazardPits::from_game(&game).is_err());
+        game.turn = 21;
+        game.board.hazards = vec![];
+        assert!(HazardPits::from_game(&game).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+//! The royale ruleset's shrinking safe area. Every `shrink_every_n_turns` turns the engine picks
+//! one of the four edges of the safe area uniformly at random and fills it with hazards. The
+//! random number generator is seeded per game, so the side can't be known ahead of time and the
+//! forecast is a distribution over the sides that could shrink.
+
+use std::collections::HashSet;
+use std::error::Error;
+
+use crate::{
+    types::Move,
+    wire_representation::{Game, Position},
+};
+
+use super::{royale_cadence, ForwardOnlyHazardAlgorithm};
+
+/// One of the possible outcomes of a shrink
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoyaleShrink {
+    /// the edge of the safe area that moves in, None when the picked edge can't move any further
+    /// because the safe area is a single cell wide in that direction
+    pub side: Option<Move>,
+    /// how likely this outcome is, the probabilities of all outcomes of a shrink sum to 1
+    pub probability: f64,
+    /// the cells that become hazards
+    pub new_hazards: Vec<Position>,
+}
+
+/// Royale hazard algorithm, forecasting which sides of the safe area can shrink next
+#[derive(Debug, Copy, Clone)]
+pub struct RoyaleHazard {
+    shrink_every_n_turns: Option<u16>,
+    min_x: i32,
+    max_x: i32,
+    min_y: i32,
+    max_y: i32,
+    current_turn: u16,
+    observed: bool,
+}
+
+impl RoyaleHazard {
+    /// Construct an uninitialized royale hazard algorithm, which reads how often the safe area
+    /// shrinks from the royale settings of the first game it observes
+    pub fn new() -> Self {
+        RoyaleHazard {
+            shrink_every_n_turns: None,
+            min_x: 0,
+            max_x: 0,
+            min_y: 0,
+            max_y: 0,
+            current_turn: 0,
+            observed: false,
+        }
+    }
+
+    /// Construct an uninitialized royale hazard algorithm that shrinks the safe area every
+    /// `shrink_every_n_turns` turns, whatever the observed game's settings say. Panics if
+    /// `shrink_every_n_turns` is 0
+    pub fn with_shrink_every_n_turns(shrink_every_n_turns: u16) -> Self {
+        assert!(
+            shrink_every_n_turns > 0,
+            "the safe area has to shrink every 1 or more turns"
+        );
+        RoyaleHazard {
+            shrink_every_n_turns: Some(shrink_every_n_turns),
+            ..Self::new()
+        }
+    }
+
+    /// the bottom left and top right corners of the current safe area
+    pub fn safe_area(&self) -> (Position, Position) {
+        (
+            Position::new(self.min_x, self.min_y),
+            Position::new(self.max_x, self.max_y),
+        )
+    }
+
+    /// The possible outcomes of the next shrink of the current safe area. Sides that can't
+    /// shrink any further are merged into a single outcome without new hazards
+    pub fn forecast(&self) -> Vec<RoyaleShrink> {
+        let mut blocked = 0;
+        let mut outcomes = vec![];
+        for side in [Move::Left, Move::Right, Move::Down, Move::Up] {
+            let new_hazards = match side {
+                Move::Left | Move::Right if self.min_x < self.max_x => {
+                    let x = if side == Move::Left {
+                        self.min_x
+                    } else {
+                        self.max_x
+                    };
+                    (self.min_y..=self.max_y)
+                        .map(|y| Position::new(x, y))
+                        .collect::<Vec<_>>()
+                }
+                Move::Down | Move::Up if self.min_y < self.max_y => {
+                    let y = if side == Move::Down {
+                        self.min_y
+                    } else {
+                        self.max_y
+                    };
+                    (self.min_x..=self.max_x)
+                        .map(|x| Position::new(x, y))
+                        .collect::<Vec<_>>()
+                }
+                _ => {
+                    blocked += 1;
+                    continue;
+                }
+            };
+            outcomes.push(RoyaleShrink {
+                side: Some(side),
+                probability: 0.25,
+                new_hazards,
+            });
+        }
+        if blocked > 0 {
+            outcomes.push(RoyaleShrink {
+                side: None,
+                probability: 0.25 * blocked as f64,
+                new_hazards: vec![],
+            });
+        }
+        outcomes
+    }
+
+    /// Follow one of the outcomes returned by `forecast` or `inc_turn`, shrinking the safe area
+    pub fn apply(&mut self, shrink: &RoyaleShrink) {
+        match shrink.side {
+            Some(Move::Left) if self.min_x < self.max_x => self.min_x += 1,
+            Some(Move::Right) if self.min_x < self.max_x => self.max_x -= 1,
+            Some(Move::Down) if self.min_y < self.max_y => self.min_y += 1,
+            Some(Move::Up) if self.min_y < self.max_y => self.max_y -= 1,
+            _ => {}
+        }
+    }
+
+    fn is_hazard(&self, position: Position) -> bool {
+        position.x < self.min_x
+            || position.x > self.max_x
+            || position.y < self.min_y
+            || position.y > self.max_y
+    }
+}
+
+impl Default for RoyaleHazard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ForwardOnlyHazardAlgorithm<RoyaleShrink> for RoyaleHazard {
+    /// works out the safe area from the game's hazards, which have to cover everything outside
+    /// of a rectangle. Unlike the spiral this can be called on every turn, to follow the shrinks
+    /// the engine actually picked. The returned iterator is every current hazard
+    fn observe(
+        &mut self,
+        game: &Game,
+    ) -> Result<Box<dyn Iterator<Item = Position>>, Box<dyn Error>> {
+        let shrink_every_n_turns = self
+            .shrink_every_n_turns
+            .or_else(|| royale_cadence(game))
+            .filter(|n| *n > 0)
+            .ok_or("no royale shrink_every_n_turns setting")?;
+
+        let width = game.board.width as i32;
+        let height = game.board.height as i32;
+        let hazards = game.board.hazards.iter().copied().collect::<HashSet<_>>();
+        let safe = (0..width)
+            .flat_map(|x| (0..height).map(move |y| Position::new(x, y)))
+            .filter(|p| !hazards.contains(p))
+            .collect::<Vec<_>>();
+        if safe.is_empty() {
+            return Err("the whole board is hazardous".into());
+        }
+
+        let observed = RoyaleHazard {
+            shrink_every_n_turns: Some(shrink_every_n_turns),
+            min_x: safe.iter().map(|p| p.x).min().unwrap(),
+            max_x: safe.iter().map(|p| p.x).max().unwrap(),
+            min_y: safe.iter().map(|p| p.y).min().unwrap(),
+            max_y: safe.iter().map(|p| p.y).max().unwrap(),
+            current_turn: game.turn as u16,
+            observed: true,
+        };
+        // any hazard inside the bounds of the safe cells leaves a hole in the rectangle
+        let area = (observed.max_x - observed.min_x + 1) * (observed.max_y - observed.min_y + 1);
+        if safe.len() as i32 != area {
+            return Err("hazards don't surround a rectangular safe area".into());
+        }
+        *self = observed;
+
+        let hazards = (0..width)
+            .flat_map(|x| (0..height).map(move |y| Position::new(x, y)))
+            .filter(|p| self.is_hazard(*p))
+            .collect::<Vec<_>>();
+        Ok(Box::new(hazards.into_iter()))
+    }
+
+    fn is_ready_for_inc(&self) -> bool {
+        self.observed
+    }
+
+    /// On turns where the safe area shrinks this returns every possible outcome, otherwise
+    /// nothing. The safe area isn't changed, use `apply` to follow one of the outcomes or
+    /// `observe` the next game to follow the one the engine picked
+    fn inc_turn(&mut self) -> Box<dyn Iterator<Item = RoyaleShrink>> {
+        self.current_turn += 1;
+        match self.shrink_every_n_turns {
+            Some(n) if self.current_turn % n == 0 => Box::new(self.forecast().into_iter()),
+            _ => Box::new(std::iter::empty()),
+        }
+    }
+
+    fn current_turn(&self) -> usize {
+        self.current_turn as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_fixture;
+
+    #[test]
+    fn test_forecasts_every_side() {
+        let game = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        assert!(RoyaleHazard::new().observe(&game).is_err());
+
+        let mut royale = RoyaleHazard::with_shrink_every_n_turns(20);
+        let hazards = royale.observe(&game).unwrap().collect::<HashSet<_>>();
+        assert_eq!(
+            hazards,
+            game.board.hazards.iter().copied().collect::<HashSet<_>>()
+        );
+        assert_eq!(
+            royale.safe_area(),
+            (Position::new(3, 0), Position::new(10, 10))
+        );
+
+        for _ in 61..80 {
+            assert_eq!(royale.inc_turn().count(), 0);
+        }
+        let outcomes = royale.inc_turn().collect::<Vec<_>>();
+        assert_eq!(royale.current_turn(), 80);
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|o| (o.side, o.new_hazards.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some(Move::Left), 11),
+                (Some(Move::Right), 11),
+                (Some(Move::Down), 8),
+                (Some(Move::Up), 8)
+            ]
+        );
+        assert_eq!(outcomes.iter().map(|o| o.probability).sum::<f64>(), 1.0);
+        assert!(outcomes[0].new_hazards.iter().all(|p| p.x == 3));
+
+        royale.apply(&outcomes[0]);
+        assert_eq!(
+            royale.safe_area(),
+            (Position::new(4, 0), Position::new(10, 10))
+        );
+    }
+
+    #[test]
+    fn test_blocked_sides_merge() {
+        let mut game = game_fixture(include_str!("../../fixtures/start_of_game.json"));
+        game.turn = 40;
+        game.board.hazards = (0..11)
+            .flat_map(|x| (0..11).map(move |y| Position::new(x, y)))
+            .filter(|p| p.x != 5 || p.y < 3 || p.y > 6)
+            .collect();
+        let mut royale = RoyaleHazard::with_shrink_every_n_turns(10);
+        assert_eq!(royale.observe(&game).unwrap().count(), 11 * 11 - 4);
+        let outcomes = royale.forecast();
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[2].side, None);
+        assert_eq!(outcomes[2].probability, 0.5);
+        assert!(outcomes[2].new_hazards.is_empty());
+
+        game.board.hazards.push(Position::new(5, 5));
+        assert!(royale.observe(&game).is_err());
+    }
+}
@@ -0,0 +1,501 @@
+//! Simulating boards together with a hazard algorithm, so lookahead sees the hazards that
+//! spawn while it searches instead of only the ones on the observed board.
+//!
+//! ```no_run
+//! use std::convert::TryFrom;
+//!
+//! use battlesnake_game_types::compact_representation::WrappedCellBoard4Snakes11x11;
+//! use battlesnake_game_types::hazard_algorithms::{ForwardOnlyHazardAlgorithm, SpiralHazard};
+//! use battlesnake_game_types::types::{SimulableGame, SimulatorInstruments, SnakeIDGettableGame};
+//! use battlesnake_game_types::wire_representation::Game;
+//! # #[derive(Debug)]
+//! # struct Instruments;
+//! # impl SimulatorInstruments for Instruments {
+//! #     fn observe_simulation(&self, _: std::time::Duration) {}
+//! # }
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let game: Game = serde_json::from_str("")?;
+//! let mut spiral = SpiralHazard::new();
+//! let _ = spiral.observe(&game)?;
+//! let board = WrappedCellBoard4Snakes11x11::try_from(game)?.with_hazard_algorithm(spiral);
+//! for (_action, child) in board.simulate(&Instruments, board.get_snake_ids()) {
+//!     // child.board() has the hazards that spawn on the next turn
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::borrow::Borrow;
+
+use rand::Rng;
+
+use crate::{
+    types::{
+        Action, FoodGettableGame, FoodQueryableGame, GameOutcome, HazardQueryableGame,
+        HazardSettableGame, HeadGettableGame, HealthGettableGame, LengthGettableGame, Move,
+        NeckQueryableGame, NeighborDeterminableGame, PositionGettableGame,
+        RandomReasonableMovesGame, SimulableGame, SimulatorInstruments, SizeDeterminableGame,
+        SnakeBodyGettableGame, SnakeIDGettableGame, SquadQueryableGame, VictorDeterminableGame,
+        YouDeterminableGame,
+    },
+    wire_representation::{Position, SquadSettings},
+};
+
+use super::ForwardOnlyHazardAlgorithm;
+
+/// A board paired with a hazard algorithm wound to the board's turn. Simulating it advances the
+/// algorithm by a turn for every child and sets the hazards it spawns on the child board.
+///
+/// Only algorithms that spawn known cells can be attached. An algorithm that isn't ready for
+/// `inc_turn` yet adds no hazards, so observe a game before attaching it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HazardForecastingGame<G, H> {
+    board: G,
+    hazards: H,
+}
+
+impl<G, H> HazardForecastingGame<G, H>
+where
+    G: HazardSettableGame + SizeDeterminableGame,
+    H: ForwardOnlyHazardAlgorithm<Position>,
+{
+    /// Attach a hazard algorithm to a board, the algorithm has to be at the board's turn
+    pub fn new(board: G, hazards: H) -> Self {
+        HazardForecastingGame { board, hazards }
+    }
+
+    /// the board, with every hazard forecast so far
+    pub fn board(&self) -> &G {
+        &self.board
+    }
+
+    /// the hazard algorithm, wound to the board's turn
+    pub fn hazard_algorithm(&self) -> &H {
+        &self.hazards
+    }
+
+    /// Detach the hazard algorithm, returning the board and the algorithm
+    pub fn into_parts(self) -> (G, H) {
+        (self.board, self.hazards)
+    }
+
+    /// moves the algorithm to the next turn and sets the hazards it spawns on `board`, which is
+    /// a child of this game
+    fn advance(&self, mut board: G) -> Self {
+        let mut hazards = self.hazards.clone();
+        if hazards.is_ready_for_inc() {
+            let width = board.get_width() as i32;
+            let height = board.get_height() as i32;
+            for position in hazards.inc_turn() {
+                // spirals keep growing past the edges of the board
+                if position.x < 0 || position.y < 0 || position.x >= width || position.y >= height {
+                    continue;
+                }
+                let native = board.native_from_position(position);
+                board.set_hazard(native);
+            }
+        }
+        HazardForecastingGame { board, hazards }
+    }
+}
+
+impl<G, H> SnakeIDGettableGame for HazardForecastingGame<G, H>
+where
+    G: SnakeIDGettableGame,
+{
+    type SnakeIDType = G::SnakeIDType;
+
+    fn get_snake_ids(&self) -> Vec<Self::SnakeIDType> {
+        self.board.get_snake_ids()
+    }
+}
+
+// the board traits below only read the board, so they are answered by it directly. Rollouts
+// and evaluations can run on the forecasting game the same way they would on the board
+
+impl<G, H> YouDeterminableGame for HazardForecastingGame<G, H>
+where
+    G: YouDeterminableGame,
+    H: std::fmt::Debug,
+{
+    fn is_you(&self, snake_id: &Self::SnakeIDType) -> bool {
+        self.board.is_you(snake_id)
+    }
+
+    fn you_id(&self) -> &Self::SnakeIDType {
+        self.board.you_id()
+    }
+}
+
+impl<G, H> VictorDeterminableGame for HazardForecastingGame<G, H>
+where
+    G: VictorDeterminableGame,
+    H: std::fmt::Debug,
+{
+    fn is_over(&self) -> bool {
+        self.board.is_over()
+    }
+
+    fn get_outcome(&self) -> GameOutcome<Self::SnakeIDType> {
+        self.board.get_outcome()
+    }
+
+    fn alive_snake_count(&self) -> usize {
+        self.board.alive_snake_count()
+    }
+}
+
+impl<G, H> HealthGettableGame for HazardForecastingGame<G, H>
+where
+    G: HealthGettableGame,
+{
+    type HealthType = G::HealthType;
+    const ZERO: Self::HealthType = G::ZERO;
+
+    fn get_health(&self, snake_id: &Self::SnakeIDType) -> Self::HealthType {
+        self.board.get_health(snake_id)
+    }
+
+    fn get_health_i64(&self, snake_id: &Self::SnakeIDType) -> i64 {
+        self.board.get_health_i64(snake_id)
+    }
+}
+
+impl<G, H> LengthGettableGame for HazardForecastingGame<G, H>
+where
+    G: LengthGettableGame,
+{
+    type LengthType = G::LengthType;
+
+    fn get_length(&self, snake_id: &Self::SnakeIDType) -> Self::LengthType {
+        self.board.get_length(snake_id)
+    }
+
+    fn get_length_i64(&self, snake_id: &Self::SnakeIDType) -> i64 {
+        self.board.get_length_i64(snake_id)
+    }
+}
+
+impl<G, H> RandomReasonableMovesGame for HazardForecastingGame<G, H>
+where
+    G: RandomReasonableMovesGame,
+{
+    fn random_reasonable_move_for_each_snake<'a>(
+        &'a self,
+        rng: &'a mut impl Rng,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a> {
+        self.board.random_reasonable_move_for_each_snake(rng)
+    }
+
+    fn reasonable_moves_for_snake(&self, snake_id: &Self::SnakeIDType) -> Vec<Move> {
+        self.board.reasonable_moves_for_snake(snake_id)
+    }
+}
+
+impl<G, H> SquadQueryableGame for HazardForecastingGame<G, H>
+where
+    G: SquadQueryableGame,
+{
+    fn get_squad_settings(&self) -> Option<SquadSettings> {
+        self.board.get_squad_settings()
+    }
+
+    fn is_teammate(&self, a: &Self::SnakeIDType, b: &Self::SnakeIDType) -> bool {
+        self.board.is_teammate(a, b)
+    }
+}
+
+impl<G, H> PositionGettableGame for HazardForecastingGame<G, H>
+where
+    G: PositionGettableGame,
+{
+    type NativePositionType = G::NativePositionType;
+
+    fn position_is_snake_body(&self, pos: Self::NativePositionType) -> bool {
+        self.board.position_is_snake_body(pos)
+    }
+
+    fn position_from_native(&self, native: Self::NativePositionType) -> Position {
+        self.board.position_from_native(native)
+    }
+
+    fn native_from_position(&self, pos: Position) -> Self::NativePositionType {
+        self.board.native_from_position(pos)
+    }
+
+    fn off_board(&self, pos: Position) -> bool {
+        self.board.off_board(pos)
+    }
+}
+
+impl<G, H> HeadGettableGame for HazardForecastingGame<G, H>
+where
+    G: HeadGettableGame,
+{
+    fn get_head_as_position(&self, snake_id: &Self::SnakeIDType) -> Position {
+        self.board.get_head_as_position(snake_id)
+    }
+
+    fn get_head_as_native_position(
+        &self,
+        snake_id: &Self::SnakeIDType,
+    ) -> Self::NativePositionType {
+        self.board.get_head_as_native_position(snake_id)
+    }
+}
+
+impl<G, H> FoodGettableGame for HazardForecastingGame<G, H>
+where
+    G: FoodGettableGame,
+{
+    fn get_all_food_as_positions(&self) -> Vec<Position> {
+        self.board.get_all_food_as_positions()
+    }
+
+    fn get_all_food_as_native_positions(&self) -> Vec<Self::NativePositionType> {
+        self.board.get_all_food_as_native_positions()
+    }
+}
+
+impl<G, H> FoodQueryableGame for HazardForecastingGame<G, H>
+where
+    G: FoodQueryableGame,
+{
+    fn is_food(&self, pos: &Self::NativePositionType) -> bool {
+        self.board.is_food(pos)
+    }
+}
+
+impl<G, H> HazardQueryableGame for HazardForecastingGame<G, H>
+where
+    G: HazardQueryableGame,
+{
+    fn is_hazard(&self, pos: &Self::NativePositionType) -> bool {
+        self.board.is_hazard(pos)
+    }
+
+    fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+        self.board.get_hazard_count(pos)
+    }
+
+    fn get_hazard_damage(&self) -> i32 {
+        self.board.get_hazard_damage()
+    }
+}
+
+impl<G, H> NeckQueryableGame for HazardForecastingGame<G, H>
+where
+    G: NeckQueryableGame,
+{
+    fn is_neck(&self, sid: &Self::SnakeIDType, pos: &Self::NativePositionType) -> bool {
+        self.board.is_neck(sid, pos)
+    }
+}
+
+impl<G, H> NeighborDeterminableGame for HazardForecastingGame<G, H>
+where
+    G: NeighborDeterminableGame,
+{
+    fn neighbors<'a>(
+        &'a self,
+        pos: &Self::NativePositionType,
+    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + 'a> {
+        self.board.neighbors(pos)
+    }
+
+    fn possible_moves<'a>(
+        &'a self,
+        pos: &Self::NativePositionType,
+    ) -> Box<dyn Iterator<Item = (Move, Self::NativePositionType)> + 'a> {
+        self.board.possible_moves(pos)
+    }
+}
+
+impl<G, H> SnakeBodyGettableGame for HazardForecastingGame<G, H>
+where
+    G: SnakeBodyGettableGame,
+{
+    fn get_snake_body_vec(&self, snake_id: &Self::SnakeIDType) -> Vec<Self::NativePositionType> {
+        self.board.get_snake_body_vec(snake_id)
+    }
+
+    fn get_snake_body_iter(
+        &self,
+        snake_id: &Self::SnakeIDType,
+    ) -> Box<dyn Iterator<Item = Self::NativePositionType> + '_> {
+        self.board.get_snake_body_iter(snake_id)
+    }
+}
+
+impl<G, H> SizeDeterminableGame for HazardForecastingGame<G, H>
+where
+    G: SizeDeterminableGame,
+{
+    fn get_width(&self) -> u32 {
+        self.board.get_width()
+    }
+
+    fn get_height(&self) -> u32 {
+        self.board.get_height()
+    }
+}
+
+/// The child states of a `HazardForecastingGame`, see `SimulableGame::SimulatedStates`
+pub struct ForecastedStates<'a, I, G, H> {
+    states: I,
+    parent: &'a HazardForecastingGame<G, H>,
+}
+
+impl<'a, I, G, H> std::fmt::Debug for ForecastedStates<'a, I, G, H>
+where
+    G: std::fmt::Debug,
+    H: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ForecastedStates")
+            .field("parent", self.parent)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, I, G, H, const N_SNAKES: usize> Iterator for ForecastedStates<'a, I, G, H>
+where
+    I: Iterator<Item = (Action<N_SNAKES>, G)>,
+    G: HazardSettableGame + SizeDeterminableGame,
+    H: ForwardOnlyHazardAlgorithm<Position>,
+{
+    type Item = (Action<N_SNAKES>, HazardForecastingGame<G, H>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (action, board) = self.states.next()?;
+        Some((action, self.parent.advance(board)))
+    }
+}
+
+impl<G, H, T, const N_SNAKES: usize> SimulableGame<T, N_SNAKES> for HazardForecastingGame<G, H>
+where
+    G: SimulableGame<T, N_SNAKES> + HazardSettableGame + SizeDeterminableGame,
+    H: ForwardOnlyHazardAlgorithm<Position>,
+    T: SimulatorInstruments,
+{
+    type SimulatedStates<'a>
+        = ForecastedStates<'a, G::SimulatedStates<'a>, G, H>
+    where
+        Self: 'a;
+
+    fn simulate_with_moves<S>(
+        &self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Self::SimulatedStates<'_>
+    where
+        S: Borrow<[Move]>,
+    {
+        ForecastedStates {
+            states: self
+                .board
+                .simulate_with_moves(instruments, snake_ids_and_moves),
+            parent: self,
+        }
+    }
+
+    fn apply_action(&self, instruments: &T, action: &Action<N_SNAKES>) -> Self {
+        self.advance(self.board.apply_action(instruments, action))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::compact_representation::WrappedCellBoard4Snakes11x11;
+    use crate::debug_wrapped_frame;
+    use crate::hazard_algorithms::SpiralHazard;
+    use crate::rollout_policies::{AvoidDeadEnds, AvoidHeadToHead};
+    use crate::types::{build_snake_id_map, HazardQueryableGame, PositionGettableGame};
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    #[derive(Debug)]
+    struct Instruments;
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    fn hazards(board: &WrappedCellBoard4Snakes11x11) -> HashSet<Position> {
+        (0..11)
+            .flat_map(|x| (0..11).map(move |y| Position::new(x, y)))
+            .filter(|p| board.is_hazard(&board.native_from_position(*p)))
+            .collect()
+    }
+
+    #[test]
+    fn test_simulation_spawns_spiral_hazards() {
+        let game = debug_wrapped_frame(60);
+        let mut spiral = SpiralHazard::new();
+        let _ = spiral.observe(&game).unwrap();
+        let ids = build_snake_id_map(&game);
+        let mut forecasting = WrappedCellBoard4Snakes11x11::convert_from_game(game.clone(), &ids)
+            .unwrap()
+            .with_hazard_algorithm(spiral);
+        let without_forecast = *forecasting.board();
+
+        for turn in game.turn + 1..game.turn + 10 {
+            let children = forecasting
+                .simulate(&Instruments, forecasting.get_snake_ids())
+                .collect::<Vec<_>>();
+            assert!(!children.is_empty());
+            let expected = spiral
+                .hazards_at_turn(turn as usize)
+                .filter(|p| p.x >= 0 && p.y >= 0 && p.x < 11 && p.y < 11)
+                .collect::<HashSet<_>>();
+            for (_, child) in &children {
+                assert_eq!(hazards(child.board()), expected);
+                assert_eq!(child.hazard_algorithm().current_turn(), turn as usize);
+            }
+            forecasting = forecasting.apply_action(&Instruments, &children[0].0);
+            assert_eq!(hazards(forecasting.board()), expected);
+        }
+        assert!(hazards(forecasting.board()).len() > hazards(&without_forecast).len());
+    }
+
+    #[test]
+    fn test_rollout_through_forecasting_game() {
+        let game = debug_wrapped_frame(60);
+        let mut spiral = SpiralHazard::new();
+        let _ = spiral.observe(&game).unwrap();
+        let ids = build_snake_id_map(&game);
+        let mut forecasting = WrappedCellBoard4Snakes11x11::convert_from_game(game.clone(), &ids)
+            .unwrap()
+            .with_hazard_algorithm(spiral);
+        let policy = (AvoidHeadToHead, AvoidDeadEnds::default());
+        let mut rng = SmallRng::seed_from_u64(3);
+
+        let mut turns = 0;
+        while !forecasting.is_over() && turns < 100 {
+            let moves = forecasting
+                .policy_move_for_each_snake(&policy, &mut rng)
+                .map(|(sid, mv)| (sid, [mv]))
+                .collect::<Vec<_>>();
+            let (_, child) = forecasting
+                .simulate_with_moves(&Instruments, moves)
+                .next()
+                .unwrap();
+            forecasting = child;
+            turns += 1;
+
+            let turn = game.turn as usize + turns;
+            let expected = spiral
+                .hazards_at_turn(turn)
+                .filter(|p| !forecasting.off_board(*p))
+                .collect::<HashSet<_>>();
+            assert_eq!(hazards(forecasting.board()), expected);
+            assert_eq!(
+                forecasting.alive_snake_count(),
+                forecasting.board().alive_snake_count()
+            );
+        }
+        assert!(turns > 1);
+        assert_eq!(forecasting.get_outcome(), forecasting.board().get_outcome());
+    }
+}
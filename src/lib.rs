@@ -1,8 +1,8 @@
 #![deny(
-  warnings,
-  missing_copy_implementations,
-  missing_debug_implementations,
-  missing_docs
+    warnings,
+    missing_copy_implementations,
+    missing_debug_implementations,
+    missing_docs
 )]
 //! Types for working with [battlesnake](https://docs.battlesnake.com/).
 //! The goal is to provide simulation tooling and fast representations that
@@ -28,16 +28,57 @@
 //! vec late stage          time:   [21.124 us 21.337 us 21.592 us]
 //! Found 14 outliers among 100 measurements (14.00%)
 //! ```
+//!
+//! Enabling the `rayon` feature adds `par_simulate_with_moves` to the compact boards, which
+//! evaluates child states in parallel.
+//!
+//! Enabling the `render` feature adds the `render` module, which draws boards as SVG and whole
+//! games as an HTML replay viewer.
 
 use wire_representation::Game;
 
 pub mod compact_representation;
+pub mod hazard_algorithms;
+#[cfg(feature = "render")]
+pub mod render;
+pub mod replay;
+pub mod rollout_policies;
 pub mod types;
 pub mod wire_representation;
-pub mod hazard_algorithms;
 
 /// Loads a fixture from a given string
 pub fn game_fixture(game_fixture: &str) -> Game {
     let g: Result<Game, _> = serde_json::from_str(game_fixture);
     g.expect("the json literal is valid")
 }
+
+/// Loads frame `i` of the wrapped game recorded in `fixtures/debug_wrapped`, which has frames
+/// 1 to 193
+#[cfg(test)]
+pub(crate) fn debug_wrapped_frame(i: usize) -> Game {
+    let file_name = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(format!("fixtures/debug_wrapped/debug_game_{}.json", i));
+    serde_json::from_slice(&std::fs::read(file_name).unwrap()).unwrap()
+}
+
+/// Builds an 11x11 test game from the start of game fixture, with one snake per body given as
+/// `(x, y)` pairs head first. The first snake is you
+#[cfg(test)]
+pub(crate) fn game_with_bodies(
+    bodies: &[&[(i32, i32)]],
+    healths: &[i32],
+    food: &[(i32, i32)],
+) -> Game {
+    use wire_representation::Position;
+
+    let mut g = game_fixture(include_str!("../fixtures/start_of_game.json"));
+    g.board.snakes.truncate(bodies.len());
+    for ((snake, body), health) in g.board.snakes.iter_mut().zip(bodies).zip(healths) {
+        snake.body = body.iter().map(|(x, y)| Position::new(*x, *y)).collect();
+        snake.head = snake.body[0];
+        snake.health = *health;
+    }
+    g.board.food = food.iter().map(|(x, y)| Position::new(*x, *y)).collect();
+    g.you = g.board.snakes[0].clone();
+    g
+}
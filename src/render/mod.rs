@@ -0,0 +1,362 @@
+//! Offline rendering of boards as SVG, and of whole games as a self-contained HTML replay
+//! viewer. Works for the wire representation and for the compact boards, so lost games can be
+//! reviewed straight from a `Replay` without the web board.
+//!
+//! ```no_run
+//! use battlesnake_game_types::render;
+//! use battlesnake_game_types::replay::Replay;
+//! use battlesnake_game_types::wire_representation::Game;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let reader = std::io::BufReader::new(std::fs::File::open("game.jsonl")?);
+//! let replay = Replay::<Game, 4>::read_json_lines(reader)?;
+//! std::fs::write("game.html", render::html_replay(replay.frames()))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write;
+
+use crate::types::{
+    FoodGettableGame, HazardQueryableGame, HealthGettableGame, SizeDeterminableGame,
+    SnakeBodyGettableGame, SnakeIDGettableGame, YouDeterminableGame,
+};
+use crate::wire_representation::Position;
+
+/// the size of a single cell in pixels
+const CELL: i32 = 24;
+/// the height of a single line of the legend in pixels
+const LEGEND_LINE: i32 = 18;
+const COLORS: [&str; 8] = [
+    "#2ca02c", "#1f77b4", "#9467bd", "#ff7f0e", "#17becf", "#d62728", "#8c564b", "#e377c2",
+];
+
+/// Everything that can be drawn. Implemented for every game that can report its snakes, food
+/// and hazards, which includes `Game` and the compact boards
+pub trait RenderableGame:
+    SizeDeterminableGame
+    + SnakeIDGettableGame
+    + SnakeBodyGettableGame
+    + HealthGettableGame
+    + FoodGettableGame
+    + HazardQueryableGame
+    + YouDeterminableGame
+{
+}
+
+impl<G> RenderableGame for G where
+    G: SizeDeterminableGame
+        + SnakeIDGettableGame
+        + SnakeBodyGettableGame
+        + HealthGettableGame
+        + FoodGettableGame
+        + HazardQueryableGame
+        + YouDeterminableGame
+{
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// the snake id as it would be printed in json, without the quotes for string ids
+fn id_label<G: RenderableGame>(id: &G::SnakeIDType) -> String {
+    match serde_json::to_value(id) {
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(value) => value.to_string(),
+        Err(_) => format!("{:?}", id),
+    }
+}
+
+/// the center of a cell in svg coordinates, the svg y axis points down
+fn center(p: Position, height: u32) -> (i32, i32) {
+    (
+        p.x * CELL + CELL / 2,
+        (height as i32 - 1 - p.y) * CELL + CELL / 2,
+    )
+}
+
+/// Draws a board as a standalone SVG document, with a legend of the living snakes underneath
+pub fn svg<G: RenderableGame>(game: &G) -> String {
+    svg_with_color_order(game, &game.get_snake_ids())
+}
+
+/// draws a board with each snake colored by where its id is in `color_order`, so that a snake
+/// keeps its color when others die or are dropped from the board
+fn svg_with_color_order<G: RenderableGame>(game: &G, color_order: &[G::SnakeIDType]) -> String {
+    let width = game.get_width();
+    let height = game.get_height();
+    let snakes = game
+        .get_snake_ids()
+        .into_iter()
+        .filter(|id| game.get_health_i64(id) > 0)
+        .map(|id| (color_order.iter().position(|o| *o == id).unwrap_or(0), id))
+        .collect::<Vec<_>>();
+    let board_width = width as i32 * CELL;
+    let board_height = height as i32 * CELL;
+    let total_height = board_height + LEGEND_LINE * (snakes.len() as i32 + 1);
+
+    let mut out = String::new();
+    // writing to a String can't fail
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="monospace" font-size="12">"#,
+        w = board_width,
+        h = total_height
+    );
+    let _ = writeln!(
+        out,
+        r##"<rect width="{}" height="{}" fill="#f4f4f4"/>"##,
+        board_width, board_height
+    );
+
+    for y in 0..height as i32 {
+        for x in 0..width as i32 {
+            let position = Position::new(x, y);
+            let (cx, cy) = center(position, height);
+            let _ = writeln!(
+                out,
+                r##"<rect class="cell" x="{}" y="{}" width="{}" height="{}" fill="none" stroke="#dddddd"/>"##,
+                cx - CELL / 2,
+                cy - CELL / 2,
+                CELL,
+                CELL
+            );
+            let hazards = game.get_hazard_count(&game.native_from_position(position));
+            if hazards > 0 {
+                let _ = writeln!(
+                    out,
+                    r##"<rect class="hazard" x="{}" y="{}" width="{}" height="{}" fill="#7f3fbf" fill-opacity="{:.2}"/>"##,
+                    cx - CELL / 2,
+                    cy - CELL / 2,
+                    CELL,
+                    CELL,
+                    (0.25 * hazards as f32).min(0.75)
+                );
+            }
+        }
+    }
+
+    for food in game.get_all_food_as_positions() {
+        let (cx, cy) = center(food, height);
+        let _ = writeln!(
+            out,
+            r##"<circle class="food" cx="{}" cy="{}" r="{}" fill="#e0245e"/>"##,
+            cx,
+            cy,
+            CELL / 4
+        );
+    }
+
+    for (idx, id) in &snakes {
+        let color = COLORS[idx % COLORS.len()];
+        let body = game
+            .get_snake_body_vec(id)
+            .into_iter()
+            .map(|p| game.position_from_native(p))
+            .collect::<Vec<_>>();
+        // segments that aren't next to each other crossed the edge of a wrapped board, so the
+        // body is drawn as one line per pair of segments
+        for (a, b) in body.iter().zip(body.iter().skip(1)) {
+            if (a.x - b.x).abs() + (a.y - b.y).abs() != 1 {
+                continue;
+            }
+            let (x1, y1) = center(*a, height);
+            let (x2, y2) = center(*b, height);
+            let _ = writeln!(
+                out,
+                r#"<line class="body" x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+                x1,
+                y1,
+                x2,
+                y2,
+                color,
+                CELL * 2 / 3
+            );
+        }
+        if let Some(head) = body.first() {
+            let (cx, cy) = center(*head, height);
+            let stroke = if game.is_you(id) { "#000000" } else { color };
+            let _ = writeln!(
+                out,
+                r#"<circle class="head" cx="{}" cy="{}" r="{}" fill="{}" stroke="{}" stroke-width="2"/>"#,
+                cx,
+                cy,
+                CELL / 2 - 2,
+                color,
+                stroke
+            );
+        }
+    }
+
+    for (line, (idx, id)) in snakes.iter().enumerate() {
+        let y = board_height + LEGEND_LINE * (line as i32 + 1);
+        let you = if game.is_you(id) { " (you)" } else { "" };
+        let _ = writeln!(
+            out,
+            r#"<rect x="4" y="{}" width="10" height="10" fill="{}"/><text x="20" y="{}">{}{} health {} length {}</text>"#,
+            y - 9,
+            COLORS[idx % COLORS.len()],
+            y,
+            escape(&id_label::<G>(id)),
+            you,
+            game.get_health_i64(id),
+            game.get_snake_body_vec(id).len()
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Renders a sequence of frames into a single HTML page that steps through them, with no
+/// external resources. The arrow keys move between frames and space plays the game
+pub fn html_replay<G: RenderableGame>(frames: &[G]) -> String {
+    // the wire representation drops eliminated snakes, so colors follow the order snakes first
+    // appear in rather than their place in each frame
+    let mut color_order = Vec::new();
+    for id in frames.iter().flat_map(|frame| frame.get_snake_ids()) {
+        if !color_order.contains(&id) {
+            color_order.push(id);
+        }
+    }
+    let svgs = frames
+        .iter()
+        .map(|frame| svg_with_color_order(frame, &color_order))
+        .collect::<Vec<_>>();
+    // serializing a list of strings can't fail
+    let frames_json = serde_json::to_string(&svgs).unwrap().replace("</", "<\\/");
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>battlesnake replay</title>
+<style>
+body {{ font-family: monospace; margin: 1em; }}
+#controls {{ margin-bottom: 1em; }}
+#frame svg {{ zoom: 1.5; }}
+</style>
+</head>
+<body>
+<div id="controls">
+<button id="prev">&lt;</button>
+<button id="play">play</button>
+<button id="next">&gt;</button>
+<input id="slider" type="range" min="0" max="{max}" value="0">
+<span id="label"></span>
+</div>
+<div id="frame"></div>
+<script>
+const frames = {frames};
+let current = 0;
+let timer = null;
+const slider = document.getElementById("slider");
+function show(i) {{
+  current = Math.max(0, Math.min(frames.length - 1, i));
+  document.getElementById("frame").innerHTML = frames[current];
+  document.getElementById("label").textContent = "frame " + current + " / " + (frames.length - 1);
+  slider.value = current;
+}}
+function toggle() {{
+  if (timer) {{
+    clearInterval(timer);
+    timer = null;
+  }} else {{
+    timer = setInterval(() => {{
+      if (current >= frames.length - 1) {{ toggle(); }} else {{ show(current + 1); }}
+    }}, 250);
+  }}
+  document.getElementById("play").textContent = timer ? "pause" : "play";
+}}
+document.getElementById("prev").onclick = () => show(current - 1);
+document.getElementById("next").onclick = () => show(current + 1);
+document.getElementById("play").onclick = toggle;
+slider.oninput = () => show(Number(slider.value));
+document.onkeydown = (e) => {{
+  if (e.key === "ArrowLeft") show(current - 1);
+  if (e.key === "ArrowRight") show(current + 1);
+  if (e.key === " ") {{ e.preventDefault(); toggle(); }}
+}};
+show(0);
+</script>
+</body>
+</html>
+"#,
+        max = frames.len().saturating_sub(1),
+        frames = frames_json
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::StandardCellBoard4Snakes11x11;
+    use crate::game_fixture;
+    use crate::types::build_snake_id_map;
+
+    #[test]
+    fn test_svg_draws_every_piece() {
+        let game = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        let drawn = svg(&game);
+        assert!(drawn.starts_with("<svg"));
+        assert!(drawn.ends_with("</svg>\n"));
+        assert_eq!(drawn.matches(r#"class="cell""#).count(), 11 * 11);
+        assert_eq!(
+            drawn.matches(r#"class="food""#).count(),
+            game.board.food.len()
+        );
+        assert_eq!(drawn.matches(r#"class="head""#).count(), 4);
+        assert!(drawn.contains("gs_MMxyjByhGFbtGSV8KJv3tqdV (you) health 86 length 10"));
+
+        let ids = build_snake_id_map(&game);
+        let compact = StandardCellBoard4Snakes11x11::convert_from_game(game.clone(), &ids).unwrap();
+        let compact_drawn = svg(&compact);
+        assert_eq!(compact_drawn.matches(r#"class="head""#).count(), 4);
+        assert_eq!(
+            compact_drawn.matches(r#"class="hazard""#).count(),
+            drawn.matches(r#"class="hazard""#).count()
+        );
+        assert!(compact_drawn.contains("0 (you) health 86 length 10"));
+    }
+
+    #[test]
+    fn test_svg_colors_survive_deaths() {
+        let mut game = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        game.board.snakes[0].health = 0;
+        let drawn = svg(&game);
+        assert_eq!(drawn.matches(r#"class="head""#).count(), 3);
+        assert!(!drawn.contains(COLORS[0]));
+        for color in &COLORS[1..4] {
+            assert!(drawn.contains(color));
+        }
+    }
+
+    #[test]
+    fn test_html_replay_embeds_every_frame() {
+        let game = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        let mut later = game.clone();
+        later.board.snakes.truncate(2);
+        let page = html_replay(&[game, later]);
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains(r#"max="1""#));
+        assert_eq!(page.matches("<svg").count(), 2);
+        assert!(!page.contains("</svg>"));
+    }
+
+    #[test]
+    fn test_html_replay_colors_survive_removed_snakes() {
+        let game = game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        let mut later = game.clone();
+        later.board.snakes.remove(0);
+        let page = html_replay(&[game, later]);
+        let later_frame = page.split("<svg").nth(2).unwrap();
+        // the frames are embedded as json strings, which escapes their quotes
+        assert_eq!(later_frame.matches(r#"class=\"head\""#).count(), 3);
+        assert!(!later_frame.contains(COLORS[0]));
+        for color in &COLORS[1..4] {
+            assert!(later_frame.contains(color));
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Rules conformance checks against games recorded from the engine. Every consecutive pair of
+//! frames is converted to a compact board, the moves made between them are extracted, and
+//! `simulate_with_moves` must reproduce the second frame.
+//!
+//! Frames are compared modulo the things the engine adds after the rules run: spawned food
+//! and hazards placed by the map. Every hazard on the simulated board has to still be there in
+//! the recorded one. Snakes eliminated between two frames are dropped from the second frame, so
+//! their move can't be extracted; every move is tried for them, and one of the resulting boards
+//! has to match.
+//!
+//! The recorded game is a wrapped arena game. Standard rules only differ from wrapped ones when
+//! a snake crosses an edge of the board, so the same game also checks the standard simulator
+//! on every stretch of turns where nobody does.
+
+use std::error::Error;
+use std::{fs, path};
+
+use itertools::Itertools;
+
+use crate::compact_representation::{StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11};
+use crate::debug_wrapped_frame;
+use crate::types::{
+    build_snake_id_map, FoodGettableGame, HazardQueryableGame, HealthGettableGame, Move,
+    SimulableGame, SimulatorInstruments, SizeDeterminableGame, SnakeBodyGettableGame, SnakeIDMap,
+    SnakeId,
+};
+use crate::wire_representation::engine_export::{
+    games_from_engine_frames, EngineFrames, EngineGameResponse,
+};
+use crate::wire_representation::{Game, Position};
+
+use super::infer_action;
+
+#[derive(Debug)]
+struct Instruments {}
+
+impl SimulatorInstruments for Instruments {
+    fn observe_simulation(&self, _: std::time::Duration) {}
+}
+
+fn fixture_path(name: &str) -> path::PathBuf {
+    path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join(name)
+}
+
+/// the simulated board matches the recorded one, ignoring food the engine spawned on empty
+/// cells and hazards added by the map
+fn matches_modulo_spawns<B>(recorded: &B, simulated: &B, n_snakes: usize) -> bool
+where
+    B: HealthGettableGame<SnakeIDType = SnakeId>
+        + SnakeBodyGettableGame
+        + FoodGettableGame
+        + HazardQueryableGame
+        + SizeDeterminableGame,
+{
+    let hazards_kept = (0..simulated.get_height() as i32)
+        .flat_map(|y| (0..simulated.get_width() as i32).map(move |x| Position::new(x, y)))
+        .map(|p| simulated.native_from_position(p))
+        .all(|cell| simulated.get_hazard_count(&cell) <= recorded.get_hazard_count(&cell));
+    if !hazards_kept {
+        return false;
+    }
+    for sid in (0..n_snakes).map(|i| SnakeId(i as u8)) {
+        if recorded.get_health_i64(&sid) != simulated.get_health_i64(&sid) {
+            return false;
+        }
+        if recorded.get_health_i64(&sid) > 0
+            && recorded.get_snake_body_vec(&sid) != simulated.get_snake_body_vec(&sid)
+        {
+            return false;
+        }
+    }
+    let recorded_food = recorded.get_all_food_as_native_positions();
+    let simulated_food = simulated.get_all_food_as_native_positions();
+    simulated_food.iter().all(|f| recorded_food.contains(f))
+        && recorded_food
+            .iter()
+            .filter(|f| !simulated_food.contains(f))
+            .all(|f| !simulated.position_is_snake_body(f.clone()))
+}
+
+/// checks every transition of a recorded game, returning the first turn the simulator disagrees
+/// with the engine on
+fn check_recorded_game<B>(
+    frames: &[Game],
+    convert: impl Fn(Game, &SnakeIDMap) -> Result<B, Box<dyn Error>>,
+) -> Result<(), String>
+where
+    B: SimulableGame<Instruments, 4>
+        + HealthGettableGame<SnakeIDType = SnakeId>
+        + SnakeBodyGettableGame
+        + FoodGettableGame
+        + HazardQueryableGame
+        + SizeDeterminableGame,
+{
+    let instruments = Instruments {};
+    let ids = build_snake_id_map(&frames[0]);
+    for pair in frames.windows(2) {
+        let turn = pair[0].turn;
+        let action = infer_action::<4>(&pair[0], &pair[1], &ids).map_err(|e| e.to_string())?;
+        let current = convert(pair[0].clone(), &ids).map_err(|e| e.to_string())?;
+        let recorded = convert(pair[1].clone(), &ids).map_err(|e| e.to_string())?;
+
+        // simulate_with_moves drops moves that kill a snake outright when it has others, so
+        // every candidate move for an eliminated snake is simulated on its own
+        let candidates = current
+            .get_snake_ids()
+            .into_iter()
+            .filter(|sid| current.get_health_i64(sid) > 0)
+            .map(|sid| match action.iter().find(|(id, _)| *id == sid) {
+                Some((_, mv)) => vec![(sid, mv)],
+                None => Move::all().iter().map(|mv| (sid, *mv)).collect(),
+            })
+            .multi_cartesian_product()
+            .collect::<Vec<_>>();
+        let reproduced = candidates.iter().any(|moves| {
+            current
+                .simulate_with_moves(
+                    &instruments,
+                    moves
+                        .iter()
+                        .map(|(sid, mv)| (*sid, std::slice::from_ref(mv))),
+                )
+                .any(|(_, simulated)| matches_modulo_spawns(&recorded, &simulated, ids.len()))
+        });
+        if !reproduced {
+            return Err(format!(
+                "simulating turn {} with {:?} does not reproduce turn {}",
+                turn, action, pair[1].turn
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn recorded_frames() -> Vec<Game> {
+    (1..=193).map(debug_wrapped_frame).collect()
+}
+
+/// does any snake that is alive in both frames cross an edge of the board between them
+fn wraps(from: &Game, to: &Game) -> bool {
+    from.board.snakes.iter().any(|before| {
+        to.board
+            .snakes
+            .iter()
+            .find(|after| after.id == before.id && after.health > 0)
+            .map_or(false, |after| {
+                (after.head.x - before.head.x).abs() + (after.head.y - before.head.y).abs() > 1
+            })
+    })
+}
+
+#[test]
+fn test_wrapped_recorded_game() {
+    check_recorded_game(
+        &recorded_frames(),
+        WrappedCellBoard4Snakes11x11::convert_from_game,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_standard_recorded_game() {
+    let mut frames = recorded_frames();
+    for frame in &mut frames {
+        frame.game.ruleset.name = "standard".to_string();
+    }
+
+    let mut checked = 0;
+    let mut start = 0;
+    for end in 1..=frames.len() {
+        if end == frames.len() || wraps(&frames[end - 1], &frames[end]) {
+            check_recorded_game(
+                &frames[start..end],
+                StandardCellBoard4Snakes11x11::convert_from_game,
+            )
+            .unwrap();
+            checked += end - start - 1;
+            start = end;
+        }
+    }
+    // more than half of the game is played without crossing an edge
+    assert!(checked > 100, "only {} turns were checked", checked);
+}
+
+/// the engine export fixture is written by hand in the engine's format, this checks the export
+/// can be read and replayed rather than any particular rule
+#[test]
+fn test_standard_engine_export() {
+    let game: EngineGameResponse =
+        serde_json::from_slice(&fs::read(fixture_path("engine_export/game.json")).unwrap())
+            .unwrap();
+    let frames: EngineFrames =
+        serde_json::from_slice(&fs::read(fixture_path("engine_export/frames.json")).unwrap())
+            .unwrap();
+    let frames = games_from_engine_frames(&game.game, &frames.frames, "gs_A").unwrap();
+    check_recorded_game(&frames, StandardCellBoard4Snakes11x11::convert_from_game).unwrap();
+}
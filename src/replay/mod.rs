@@ -0,0 +1,408 @@
+//! A replay is a whole game: an ordered list of frames, plus the action that was taken on
+//! every frame to reach the next one. Replays can be stored as JSON lines (one frame per
+//! line) and re-simulated to check that every recorded transition is reproduced by the
+//! simulator.
+//!
+//! ```no_run
+//! use battlesnake_game_types::compact_representation::WrappedCellBoard4Snakes11x11;
+//! use battlesnake_game_types::replay::Replay;
+//! use battlesnake_game_types::types::{build_snake_id_map, SimulatorInstruments};
+//! use battlesnake_game_types::wire_representation::Game;
+//! # #[derive(Debug)]
+//! # struct Instruments;
+//! # impl SimulatorInstruments for Instruments {
+//! #     fn observe_simulation(&self, _: std::time::Duration) {}
+//! # }
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let reader = std::io::BufReader::new(std::fs::File::open("game.jsonl")?);
+//! let replay = Replay::<Game, 4>::read_json_lines(reader)?;
+//! let ids = build_snake_id_map(&replay.frames()[0]);
+//! let compact = replay.map_frames(|g| WrappedCellBoard4Snakes11x11::convert_from_game(g, &ids))?;
+//! compact.verify(&Instruments)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::io::{BufRead, Write};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{Action, Move, SimulableGame, SimulatorInstruments, SnakeIDMap, Vector};
+use crate::wire_representation::Game;
+
+/// An ordered sequence of frames, along with the action taken on each frame. There is always
+/// exactly one more frame than there are actions: the final frame has no action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay<G, const N_SNAKES: usize> {
+    frames: Vec<G>,
+    actions: Vec<Action<N_SNAKES>>,
+}
+
+/// A recorded transition that the simulator does not reproduce
+#[derive(Debug, Clone)]
+pub struct TransitionMismatch<G> {
+    /// index of the frame the action was taken on
+    pub index: usize,
+    /// the next frame, as recorded in the replay
+    pub expected: G,
+    /// the next frame, as produced by the simulator
+    pub actual: G,
+}
+
+impl<G> fmt::Display for TransitionMismatch<G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "simulating frame {} does not reproduce frame {}",
+            self.index,
+            self.index + 1
+        )
+    }
+}
+
+impl<G: fmt::Debug> Error for TransitionMismatch<G> {}
+
+/// a single line of the JSON lines representation
+#[derive(Serialize, Deserialize)]
+struct ReplayLine<G, const N_SNAKES: usize> {
+    frame: G,
+    #[serde(default)]
+    action: Option<Action<N_SNAKES>>,
+}
+
+impl<G, const N_SNAKES: usize> Replay<G, N_SNAKES> {
+    /// creates a replay consisting only of its first frame
+    pub fn new(first_frame: G) -> Self {
+        Self {
+            frames: vec![first_frame],
+            actions: vec![],
+        }
+    }
+
+    /// builds a replay from its parts, `frames` must be exactly one longer than `actions`
+    pub fn from_parts(
+        frames: Vec<G>,
+        actions: Vec<Action<N_SNAKES>>,
+    ) -> Result<Self, Box<dyn Error>> {
+        if frames.len() != actions.len() + 1 {
+            return Err(format!(
+                "a replay with {} frames needs {} actions, got {}",
+                frames.len(),
+                frames.len().saturating_sub(1),
+                actions.len()
+            )
+            .into());
+        }
+        Ok(Self { frames, actions })
+    }
+
+    /// records that `action` was taken on the current last frame, producing `frame`
+    pub fn push(&mut self, action: Action<N_SNAKES>, frame: G) {
+        self.actions.push(action);
+        self.frames.push(frame);
+    }
+
+    /// all frames, in order
+    pub fn frames(&self) -> &[G] {
+        &self.frames
+    }
+
+    /// the action taken on each frame, `actions()[i]` leads from `frames()[i]` to `frames()[i + 1]`
+    pub fn actions(&self) -> &[Action<N_SNAKES>] {
+        &self.actions
+    }
+
+    /// the last frame of the replay
+    pub fn last_frame(&self) -> &G {
+        self.frames.last().expect("replays have at least one frame")
+    }
+
+    /// iterates over every (frame, action, next frame) triple
+    pub fn transitions(&self) -> impl Iterator<Item = (&G, &Action<N_SNAKES>, &G)> + '_ {
+        self.frames
+            .iter()
+            .zip(self.actions.iter())
+            .zip(self.frames.iter().skip(1))
+            .map(|((frame, action), next)| (frame, action, next))
+    }
+
+    /// converts every frame, keeping the actions. Useful for turning a replay of wire games
+    /// in to a replay of compact boards
+    pub fn map_frames<H, E>(
+        self,
+        f: impl FnMut(G) -> Result<H, E>,
+    ) -> Result<Replay<H, N_SNAKES>, E> {
+        let frames = self.frames.into_iter().map(f).collect::<Result<_, _>>()?;
+        Ok(Replay {
+            frames,
+            actions: self.actions,
+        })
+    }
+
+    /// re-simulates every transition and checks that the simulated frame matches the recorded
+    /// one, returning the first mismatch
+    pub fn verify<T>(&self, instruments: &T) -> Result<(), TransitionMismatch<G>>
+    where
+        T: SimulatorInstruments,
+        G: SimulableGame<T, N_SNAKES> + PartialEq + Clone,
+    {
+        self.verify_with(instruments, |expected, actual| expected == actual)
+    }
+
+    /// like `verify`, but with a custom comparison between the recorded (first argument) and
+    /// simulated (second argument) frames, e.g. to ignore randomly spawned food
+    pub fn verify_with<T>(
+        &self,
+        instruments: &T,
+        matches: impl Fn(&G, &G) -> bool,
+    ) -> Result<(), TransitionMismatch<G>>
+    where
+        T: SimulatorInstruments,
+        G: SimulableGame<T, N_SNAKES> + Clone,
+    {
+        for (index, (frame, action, next)) in self.transitions().enumerate() {
+            let actual = frame.apply_action(instruments, action);
+            if !matches(next, &actual) {
+                return Err(TransitionMismatch {
+                    index,
+                    expected: next.clone(),
+                    actual,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// writes the replay as JSON lines, one `{"frame": .., "action": ..}` object per frame.
+    /// The last frame has a null action
+    pub fn write_json_lines<W: Write>(&self, mut writer: W) -> Result<(), Box<dyn Error>>
+    where
+        G: Serialize,
+    {
+        for (idx, frame) in self.frames.iter().enumerate() {
+            let line = ReplayLine {
+                frame,
+                action: self.actions.get(idx).copied(),
+            };
+            serde_json::to_writer(&mut writer, &line)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// reads a replay written by `write_json_lines`, blank lines are skipped
+    pub fn read_json_lines<R: BufRead>(reader: R) -> Result<Self, Box<dyn Error>>
+    where
+        G: DeserializeOwned,
+    {
+        let mut frames = vec![];
+        let mut actions = vec![];
+        let mut pending_action = false;
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let parsed: ReplayLine<G, N_SNAKES> = serde_json::from_str(&line)
+                .map_err(|e| format!("line {}: {}", line_number + 1, e))?;
+            if !frames.is_empty() && !pending_action {
+                return Err(
+                    format!("line {}: previous frame has no action", line_number + 1).into(),
+                );
+            }
+            frames.push(parsed.frame);
+            pending_action = parsed.action.is_some();
+            actions.extend(parsed.action);
+        }
+        if frames.is_empty() {
+            return Err("a replay needs at least one frame".into());
+        }
+        if pending_action {
+            return Err("the last frame of a replay can't have an action".into());
+        }
+        Self::from_parts(frames, actions)
+    }
+}
+
+impl<const N_SNAKES: usize> Replay<Game, N_SNAKES> {
+    /// builds a replay from consecutive wire frames (e.g. the per turn requests a snake
+    /// received), inferring each snake's move from how its head moved between frames.
+    ///
+    /// Snakes that are eliminated between two frames are removed from the next frame, so their
+    /// moves can't be recovered and are left out of the action.
+    pub fn from_wire_frames(
+        frames: Vec<Game>,
+        snake_ids: &SnakeIDMap,
+    ) -> Result<Self, Box<dyn Error>> {
+        let actions = frames
+            .windows(2)
+            .map(|pair| infer_action(&pair[0], &pair[1], snake_ids))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::from_parts(frames, actions)
+    }
+}
+
+/// infers the moves that the snakes alive in both `from` and `to` made, based on head movement
+/// (wrapping around the board edges if needed)
+pub fn infer_action<const N_SNAKES: usize>(
+    from: &Game,
+    to: &Game,
+    snake_ids: &SnakeIDMap,
+) -> Result<Action<N_SNAKES>, Box<dyn Error>> {
+    if to.turn != from.turn + 1 {
+        return Err(format!("turn {} does not follow turn {}", to.turn, from.turn).into());
+    }
+    let width = from.board.width as i64;
+    let height = from.board.height as i64;
+    let mut moves = [None; N_SNAKES];
+    for snake in from.board.snakes.iter().filter(|s| s.health > 0) {
+        let next = match to
+            .board
+            .snakes
+            .iter()
+            .find(|s| s.id == snake.id && s.health > 0)
+        {
+            Some(next) => next,
+            None => continue,
+        };
+        let sid = snake_ids
+            .get(&snake.id)
+            .ok_or_else(|| format!("snake {} has no id", snake.id))?;
+        let unwrap_delta = |delta: i64, size: i64| {
+            if delta == size - 1 {
+                -1
+            } else if delta == 1 - size {
+                1
+            } else {
+                delta
+            }
+        };
+        let vector = Vector {
+            x: unwrap_delta((next.head.x - snake.head.x) as i64, width),
+            y: unwrap_delta((next.head.y - snake.head.y) as i64, height),
+        };
+        let mv = Move::all_iter()
+            .find(|mv| {
+                let v = mv.to_vector();
+                v.x == vector.x && v.y == vector.y
+            })
+            .ok_or_else(|| {
+                format!(
+                    "snake {} moved from {:?} to {:?} on turn {}",
+                    snake.id, snake.head, next.head, from.turn
+                )
+            })?;
+        *moves
+            .get_mut(sid.as_usize())
+            .ok_or_else(|| format!("snake id {} is out of range", sid.as_usize()))? = Some(mv);
+    }
+    Ok(Action::new(moves))
+}
+
+#[cfg(test)]
+mod conformance;
+
+#[cfg(test)]
+mod tests {
+
+    use rand::prelude::SmallRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::compact_representation::WrappedCellBoard4Snakes11x11;
+    use crate::debug_wrapped_frame;
+    use crate::types::{build_snake_id_map, RandomReasonableMovesGame, VictorDeterminableGame};
+
+    #[derive(Debug)]
+    struct Instruments {}
+
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: std::time::Duration) {}
+    }
+
+    fn debug_wrapped_frames(n: usize) -> Vec<Game> {
+        (1..=n).map(debug_wrapped_frame).collect()
+    }
+
+    #[test]
+    fn test_wire_frames_round_trip() {
+        let frames = debug_wrapped_frames(20);
+        let ids = build_snake_id_map(&frames[0]);
+        let replay = Replay::<Game, 4>::from_wire_frames(frames.clone(), &ids).unwrap();
+        assert_eq!(replay.frames(), frames.as_slice());
+        assert_eq!(replay.actions().len(), 19);
+        // every snake survives the opening, so every snake has a move
+        assert!(replay.actions().iter().all(|a| a.iter().count() == 4));
+
+        let mut buf = vec![];
+        replay.write_json_lines(&mut buf).unwrap();
+        assert_eq!(buf.iter().filter(|b| **b == b'\n').count(), 20);
+        let read = Replay::<Game, 4>::read_json_lines(buf.as_slice()).unwrap();
+        assert_eq!(read, replay);
+    }
+
+    #[test]
+    fn test_verify_compact_replay() {
+        let frames = debug_wrapped_frames(1);
+        let ids = build_snake_id_map(&frames[0]);
+        let start =
+            WrappedCellBoard4Snakes11x11::convert_from_game(frames[0].clone(), &ids).unwrap();
+        let instruments = Instruments {};
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut replay = Replay::new(start);
+        while !replay.last_frame().is_over() && replay.actions().len() < 50 {
+            let moves = replay
+                .last_frame()
+                .random_reasonable_move_for_each_snake(&mut rng)
+                .collect::<Vec<_>>();
+            let action = Action::collect_from(moves.iter());
+            let next = replay.last_frame().apply_action(&instruments, &action);
+            replay.push(action, next);
+        }
+        assert!(replay.actions().len() > 1);
+        replay.verify(&instruments).unwrap();
+
+        let mut buf = vec![];
+        replay.write_json_lines(&mut buf).unwrap();
+        let read =
+            Replay::<WrappedCellBoard4Snakes11x11, 4>::read_json_lines(buf.as_slice()).unwrap();
+        assert_eq!(read, replay);
+
+        // dropping a frame means the recorded transitions no longer line up
+        let mut frames = replay.frames().to_vec();
+        frames.remove(2);
+        let mut actions = replay.actions().to_vec();
+        actions.pop();
+        let broken = Replay::from_parts(frames, actions).unwrap();
+        let mismatch = broken.verify(&instruments).unwrap_err();
+        assert_eq!(mismatch.index, 1);
+    }
+
+    #[test]
+    fn test_malformed_json_lines() {
+        let frames = debug_wrapped_frames(2);
+        let line = |frame: &Game, action: &str| {
+            format!(
+                "{{\"frame\": {}, \"action\": {}}}\n",
+                serde_json::to_string(frame).unwrap(),
+                action
+            )
+        };
+        let missing_action = line(&frames[0], "null") + &line(&frames[1], "null");
+        assert!(Replay::<Game, 4>::read_json_lines(missing_action.as_bytes()).is_err());
+        let trailing_action = line(&frames[0], "[\"up\"]");
+        assert!(Replay::<Game, 4>::read_json_lines(trailing_action.as_bytes()).is_err());
+        let too_many_moves =
+            line(&frames[0], "[\"up\", null, null, null, \"left\"]") + &line(&frames[1], "null");
+        assert!(Replay::<Game, 4>::read_json_lines(too_many_moves.as_bytes()).is_err());
+        let ok = line(&frames[0], "[\"up\", null, \"Left\"]") + &line(&frames[1], "null");
+        let replay = Replay::<Game, 4>::read_json_lines(ok.as_bytes()).unwrap();
+        assert_eq!(
+            replay.actions()[0],
+            Action::new([Some(Move::Up), None, Some(Move::Left), None])
+        );
+    }
+}
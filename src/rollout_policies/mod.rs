@@ -0,0 +1,310 @@
+//! built in [RolloutPolicy] implementations, used to make random rollouts less suicidal than
+//! picking uniformly from [RandomReasonableMovesGame::reasonable_moves_for_snake]
+//!
+//! policies can be combined by putting them in a tuple, e.g.
+//! `(AvoidHeadToHead, PreferFoodWhenHungry::default(), AvoidDeadEnds::default())`
+//! which applies them in order.
+//!
+//! [RandomReasonableMovesGame::reasonable_moves_for_snake]: crate::types::RandomReasonableMovesGame::reasonable_moves_for_snake
+
+use std::collections::{HashSet, VecDeque};
+
+use crate::types::{
+    FoodGettableGame, HeadGettableGame, HealthGettableGame, LengthGettableGame, Move,
+    NeighborDeterminableGame, RolloutPolicy, SnakeBodyGettableGame,
+};
+
+/// keeps only the moves that match `keep`, unless that would remove every move
+fn retain_or_keep_all(moves: &mut Vec<Move>, keep: impl Fn(&Move) -> bool) {
+    if moves.iter().any(&keep) {
+        moves.retain(keep);
+    }
+}
+
+/// finds where each of the given moves would put the head of the given snake
+fn destinations<G: HeadGettableGame + NeighborDeterminableGame>(
+    game: &G,
+    snake_id: &G::SnakeIDType,
+    moves: &[Move],
+) -> Vec<(Move, G::NativePositionType)> {
+    let head = game.get_head_as_native_position(snake_id);
+
+    game.possible_moves(&head)
+        .filter(|(mv, _)| moves.contains(mv))
+        .collect()
+}
+
+/// Avoids moving in to any cell that the head of an equal or longer snake could also move in
+/// to this turn, as those head to heads are either lost or a draw
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AvoidHeadToHead;
+
+impl<G> RolloutPolicy<G> for AvoidHeadToHead
+where
+    G: HeadGettableGame + LengthGettableGame + NeighborDeterminableGame,
+{
+    fn retain_moves(&self, game: &G, snake_id: &G::SnakeIDType, moves: &mut Vec<Move>) {
+        let own_length = game.get_length(snake_id);
+
+        let contested: HashSet<G::NativePositionType> = game
+            .get_snake_ids()
+            .into_iter()
+            .filter(|other| other != snake_id && game.get_length(other) >= own_length)
+            .flat_map(|other| {
+                let head = game.get_head_as_native_position(&other);
+                game.neighbors(&head).collect::<Vec<_>>()
+            })
+            .collect();
+
+        if contested.is_empty() {
+            return;
+        }
+
+        let destinations = destinations(game, snake_id, moves);
+        retain_or_keep_all(moves, |mv| {
+            destinations
+                .iter()
+                .any(|(dest_mv, dest)| dest_mv == mv && !contested.contains(dest))
+        });
+    }
+}
+
+/// When a snake's health is at or below `health_threshold` only keep the moves that get it
+/// closest to the nearest food (by manhattan distance). Moves that eat food are always the
+/// closest.
+#[derive(Debug, Clone, Copy)]
+pub struct PreferFoodWhenHungry {
+    /// the health at or below which a snake is considered hungry
+    pub health_threshold: i64,
+}
+
+impl Default for PreferFoodWhenHungry {
+    fn default() -> Self {
+        Self {
+            health_threshold: 30,
+        }
+    }
+}
+
+impl<G> RolloutPolicy<G> for PreferFoodWhenHungry
+where
+    G: HeadGettableGame + HealthGettableGame + FoodGettableGame + NeighborDeterminableGame,
+{
+    fn retain_moves(&self, game: &G, snake_id: &G::SnakeIDType, moves: &mut Vec<Move>) {
+        if game.get_health_i64(snake_id) > self.health_threshold {
+            return;
+        }
+
+        let food = game.get_all_food_as_positions();
+        if food.is_empty() {
+            return;
+        }
+
+        let distances = destinations(game, snake_id, moves)
+            .into_iter()
+            .map(|(mv, dest)| {
+                let dest = game.position_from_native(dest);
+                let distance = food
+                    .iter()
+                    .map(|f| f.sub_vec(dest.to_vector()).manhattan_length())
+                    .min()
+                    .unwrap();
+                (mv, distance)
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(closest) = distances.iter().map(|(_, d)| *d).min() {
+            retain_or_keep_all(moves, |mv| {
+                distances
+                    .iter()
+                    .any(|(dest_mv, d)| dest_mv == mv && *d == closest)
+            });
+        }
+    }
+}
+
+/// Avoids moving in to pockets that are too small for the snake to fit in to. This is a cheap
+/// flood fill from each destination, treating every current snake body segment as a wall, that
+/// stops once it has found enough room for the snake (or `max_area` cells).
+///
+/// If no move has enough room, only the moves with the most room are kept.
+#[derive(Debug, Clone, Copy)]
+pub struct AvoidDeadEnds {
+    /// the most cells the flood fill will visit from each destination
+    pub max_area: usize,
+}
+
+impl Default for AvoidDeadEnds {
+    fn default() -> Self {
+        Self { max_area: 32 }
+    }
+}
+
+impl<G> RolloutPolicy<G> for AvoidDeadEnds
+where
+    G: HeadGettableGame + LengthGettableGame + NeighborDeterminableGame + SnakeBodyGettableGame,
+{
+    fn retain_moves(&self, game: &G, snake_id: &G::SnakeIDType, moves: &mut Vec<Move>) {
+        if moves.len() <= 1 {
+            return;
+        }
+
+        let needed = (game.get_length_i64(snake_id).max(0) as usize).min(self.max_area);
+        let walls: HashSet<G::NativePositionType> = game
+            .get_snake_ids()
+            .iter()
+            .flat_map(|sid| game.get_snake_body_iter(sid))
+            .collect();
+
+        let areas = destinations(game, snake_id, moves)
+            .into_iter()
+            .map(|(mv, dest)| (mv, self.reachable_area(game, &walls, dest, needed)))
+            .collect::<Vec<_>>();
+
+        let best = match areas.iter().map(|(_, area)| *area).max() {
+            Some(best) => best.min(needed),
+            None => return,
+        };
+        retain_or_keep_all(moves, |mv| {
+            areas
+                .iter()
+                .any(|(dest_mv, area)| dest_mv == mv && *area >= best)
+        });
+    }
+}
+
+impl AvoidDeadEnds {
+    /// counts the cells reachable from start, stopping early once `needed` cells have been found
+    fn reachable_area<G: NeighborDeterminableGame>(
+        &self,
+        game: &G,
+        walls: &HashSet<G::NativePositionType>,
+        start: G::NativePositionType,
+        needed: usize,
+    ) -> usize {
+        if walls.contains(&start) {
+            return 0;
+        }
+
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        seen.insert(start.clone());
+        queue.push_back(start);
+
+        while let Some(pos) = queue.pop_front() {
+            if seen.len() >= needed {
+                break;
+            }
+            for neighbor in game.neighbors(&pos) {
+                if !walls.contains(&neighbor) && seen.insert(neighbor.clone()) {
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        seen.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::SmallRng, SeedableRng};
+
+    use super::*;
+    use crate::{
+        compact_representation::StandardCellBoard4Snakes11x11,
+        game_with_bodies,
+        types::{build_snake_id_map, RandomReasonableMovesGame, SnakeId},
+        wire_representation::Game,
+    };
+
+    fn compact(g: &Game) -> StandardCellBoard4Snakes11x11 {
+        g.as_cell_board(&build_snake_id_map(g)).unwrap()
+    }
+
+    #[test]
+    fn test_avoid_head_to_head() {
+        // the longer snake 1 can reach both (5, 6) and (6, 5), so only moving left is safe
+        let g = game_with_bodies(
+            &[&[(5, 5), (5, 4), (5, 3)], &[(6, 6), (7, 6), (8, 6), (9, 6)]],
+            &[100, 100],
+            &[],
+        );
+        let c = compact(&g);
+        let mut moves = c.reasonable_moves_for_snake(&SnakeId(0));
+        assert_eq!(moves, vec![Move::Up, Move::Left, Move::Right]);
+        AvoidHeadToHead.retain_moves(&c, &SnakeId(0), &mut moves);
+        assert_eq!(moves, vec![Move::Left]);
+
+        let mut wire_moves = g.reasonable_moves_for_snake(&g.you.id);
+        AvoidHeadToHead.retain_moves(&g, &g.you.id, &mut wire_moves);
+        assert_eq!(wire_moves, vec![Move::Left]);
+
+        // the shorter snake doesn't have to avoid anything
+        let mut moves = c.reasonable_moves_for_snake(&SnakeId(1));
+        let before = moves.clone();
+        AvoidHeadToHead.retain_moves(&c, &SnakeId(1), &mut moves);
+        assert_eq!(moves, before);
+    }
+
+    #[test]
+    fn test_prefer_food_when_hungry() {
+        let g = game_with_bodies(&[&[(5, 5), (5, 4), (5, 3)]], &[10], &[(8, 5)]);
+        let c = compact(&g);
+        let policy = PreferFoodWhenHungry::default();
+
+        let mut moves = c.reasonable_moves_for_snake(&SnakeId(0));
+        policy.retain_moves(&c, &SnakeId(0), &mut moves);
+        assert_eq!(moves, vec![Move::Right]);
+
+        let full = game_with_bodies(&[&[(5, 5), (5, 4), (5, 3)]], &[90], &[(8, 5)]);
+        let c = compact(&full);
+        let mut moves = c.reasonable_moves_for_snake(&SnakeId(0));
+        policy.retain_moves(&c, &SnakeId(0), &mut moves);
+        assert_eq!(moves, vec![Move::Up, Move::Left, Move::Right]);
+    }
+
+    #[test]
+    fn test_avoid_dead_ends() {
+        // snake 1 walls off the three cells below snake 0, which is too small for it to fit in
+        let g = game_with_bodies(
+            &[
+                &[(0, 3), (0, 4), (0, 5), (0, 6)],
+                &[(1, 0), (1, 1), (1, 2), (2, 2), (3, 2)],
+            ],
+            &[100, 100],
+            &[],
+        );
+        let c = compact(&g);
+        let mut moves = c.reasonable_moves_for_snake(&SnakeId(0));
+        assert_eq!(moves, vec![Move::Down, Move::Right]);
+        AvoidDeadEnds::default().retain_moves(&c, &SnakeId(0), &mut moves);
+        assert_eq!(moves, vec![Move::Right]);
+    }
+
+    #[test]
+    fn test_policies_combine_and_never_remove_every_move() {
+        let g = game_with_bodies(
+            &[
+                &[(5, 5), (5, 4), (5, 3)],
+                &[(6, 6), (7, 6), (8, 6), (9, 6)],
+                &[(4, 6), (3, 6), (2, 6), (1, 6)],
+            ],
+            &[100, 100, 100],
+            &[],
+        );
+        let c = compact(&g);
+        let policy = (
+            AvoidHeadToHead,
+            PreferFoodWhenHungry::default(),
+            AvoidDeadEnds::default(),
+        );
+        let mut moves = c.reasonable_moves_for_snake(&SnakeId(0));
+        policy.retain_moves(&c, &SnakeId(0), &mut moves);
+        assert_eq!(moves, vec![Move::Up, Move::Left, Move::Right]);
+
+        let mut rng = SmallRng::seed_from_u64(0);
+        let chosen = c.policy_move_for_each_snake(&policy, &mut rng).count();
+        assert_eq!(chosen, 3);
+    }
+}
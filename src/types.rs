@@ -1,7 +1,9 @@
 //! various types that are useful for working with battlesnake
-use crate::wire_representation::{Game, Position};
+use crate::wire_representation::{Game, Position, SquadSettings};
+use rand::prelude::IteratorRandom;
 use rand::Rng;
-use serde::{Deserialize, Serialize, Serializer};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::fmt::{self, Debug};
@@ -25,15 +27,19 @@ pub struct Vector {
 pub const N_MOVES: usize = 4;
 
 /// Represents a move
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Move {
     #[allow(missing_docs)]
+    #[serde(alias = "left")]
     Left,
     #[allow(missing_docs)]
+    #[serde(alias = "down")]
     Down,
     #[allow(missing_docs)]
+    #[serde(alias = "up")]
     Up,
     #[allow(missing_docs)]
+    #[serde(alias = "right")]
     Right,
 }
 
@@ -198,18 +204,118 @@ pub trait YouDeterminableGame: std::fmt::Debug + SnakeIDGettableGame {
 
 /// A game which can have it's winner determined
 pub trait VictorDeterminableGame: std::fmt::Debug + SnakeIDGettableGame {
-    #[allow(missing_docs)]
+    /// is the game over for you? This is also true once you have been eliminated, and is the same
+    /// as [VictorDeterminableGame::get_outcome] not being [GameOutcome::Ongoing]
     fn is_over(&self) -> bool;
 
+    /// get the outcome of the game, from your perspective
+    fn get_outcome(&self) -> GameOutcome<Self::SnakeIDType>;
+
     /// get the winner for a given game, will return None in the case of a draw, or if the game is not over
-    fn get_winner(&self) -> Option<Self::SnakeIDType>;
+    fn get_winner(&self) -> Option<Self::SnakeIDType> {
+        match self.get_outcome() {
+            GameOutcome::Win(winner) => Some(winner),
+            GameOutcome::Draw(_)
+            | GameOutcome::SoloEnded
+            | GameOutcome::Eliminated
+            | GameOutcome::Ongoing => None,
+        }
+    }
 
     /// How many snakes are alive
     fn alive_snake_count(&self) -> usize;
 }
-#[derive(Debug, Copy, Clone)]
+
+/// The outcome of a game
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GameOutcome<S> {
+    /// the given snake (or in squad games, its squad) is the last one standing
+    Win(S),
+    /// every remaining snake was eliminated on the same turn, these are the snakes that died on
+    /// that final turn
+    Draw(Vec<S>),
+    /// a solo game ended, nobody wins those
+    SoloEnded,
+    /// you were eliminated while the others keep playing
+    Eliminated,
+    /// you are still alive, and so is more than one snake (or squad) in total
+    Ongoing,
+}
+
+/// Records the turn each snake was eliminated on, by observing a game turn by turn, so that
+/// snakes can be ranked against each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standings<S: Hash + Eq> {
+    turn_of_death: HashMap<S, Option<i32>>,
+}
+
+impl<S: Hash + Eq + Clone> Default for Standings<S> {
+    fn default() -> Self {
+        Self {
+            turn_of_death: HashMap::new(),
+        }
+    }
+}
+
+impl<S: Hash + Eq + Clone> Standings<S> {
+    /// creates standings for a game that hasn't been observed yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// records the state of the game at the given turn. Every alive snake is added to the
+    /// standings, and every snake that was alive before but isn't any more is recorded as dying
+    /// on this turn
+    pub fn observe<G: SnakeIDGettableGame<SnakeIDType = S>>(&mut self, game: &G, turn: i32) {
+        let alive = game.get_snake_ids();
+        for (sid, death) in self.turn_of_death.iter_mut() {
+            if death.is_none() && !alive.contains(sid) {
+                *death = Some(turn);
+            }
+        }
+        for sid in alive {
+            self.turn_of_death.entry(sid).or_insert(None);
+        }
+    }
+
+    /// the turn the given snake was eliminated on, `None` if it is still alive or unknown
+    pub fn turn_of_death(&self, sid: &S) -> Option<i32> {
+        self.turn_of_death.get(sid).copied().flatten()
+    }
+
+    /// where the given snake placed, starting from 1. Snakes that are still alive share first
+    /// place, and snakes that were eliminated on the same turn share a place
+    pub fn placement(&self, sid: &S) -> Option<usize> {
+        let death = *self.turn_of_death.get(sid)?;
+        let better = self
+            .turn_of_death
+            .values()
+            .filter(|other| match (other, death) {
+                (None, Some(_)) => true,
+                (Some(other), Some(death)) => *other > death,
+                _ => false,
+            })
+            .count();
+        Some(better + 1)
+    }
+
+    /// every observed snake and its placement, best first
+    pub fn placements(&self) -> Vec<(S, usize)> {
+        let mut placements = self
+            .turn_of_death
+            .keys()
+            .map(|sid| (sid.clone(), self.placement(sid).unwrap()))
+            .collect::<Vec<_>>();
+        placements.sort_by_key(|(_, placement)| *placement);
+        placements
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 /// Represents moves taken for a given simulation
+///
+/// Actions are plain `Copy` data and are always `Send + Sync`
 pub struct Action<const N_SNAKES: usize> {
     moves: [Option<Move>; N_SNAKES],
 }
@@ -239,6 +345,14 @@ impl<const N_SNAKES: usize> Action<N_SNAKES> {
     pub fn own_move(&self) -> Move {
         self.moves[0].unwrap()
     }
+    /// iterates over the snakes that have a move in this action, along with that move
+    pub fn iter(&self) -> impl Iterator<Item = (SnakeId, Move)> + '_ {
+        self.moves
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, mv)| mv.map(|mv| (SnakeId(idx as u8), mv)))
+    }
+
     /// construct an OtherAction of the other sankes moves
     pub fn other_moves(&self) -> OtherAction<N_SNAKES> {
         let mut new_moves = self.moves;
@@ -247,18 +361,47 @@ impl<const N_SNAKES: usize> Action<N_SNAKES> {
     }
 }
 
-/// a game for which future states can be simulated
+/// Actions serialize as a list of `N_SNAKES` optional moves, indexed by snake id
+impl<const N_SNAKES: usize> Serialize for Action<N_SNAKES> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.moves.as_slice().serialize(serializer)
+    }
+}
+
+/// shorter lists are padded with `None`, longer lists are rejected
+impl<'de, const N_SNAKES: usize> Deserialize<'de> for Action<N_SNAKES> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let parsed = Vec::<Option<Move>>::deserialize(deserializer)?;
+        if parsed.len() > N_SNAKES {
+            return Err(D::Error::invalid_length(
+                parsed.len(),
+                &format!("at most {} moves", N_SNAKES).as_str(),
+            ));
+        }
+        let mut moves = [None; N_SNAKES];
+        moves[..parsed.len()].copy_from_slice(&parsed);
+        Ok(Self { moves })
+    }
+}
+
+/// a game for which future states can be simulated. Actions are indexed by [SnakeId], so that is
+/// the snake id of every simulable game
 pub trait SimulableGame<T: SimulatorInstruments, const N_SNAKES: usize>:
-    std::fmt::Debug + Sized + SnakeIDGettableGame
+    std::fmt::Debug + Sized + SnakeIDGettableGame<SnakeIDType = SnakeId>
 {
+    /// the iterator of child states produced by simulating this game. This is a concrete type
+    /// so that implementations can avoid boxing and allocating for every simulation
+    type SimulatedStates<'a>: Iterator<Item = (Action<N_SNAKES>, Self)> + 'a
+    where
+        Self: 'a;
+
     /// simulates all possible future games for a given game returning the snake ids, moves that
     /// got to a given state, plus that state
-    #[allow(clippy::type_complexity)]
     fn simulate(
         &self,
         instruments: &T,
         snake_ids: Vec<Self::SnakeIDType>,
-    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + '_> {
+    ) -> Self::SimulatedStates<'_> {
         let moves_to_simulate = Move::all();
         let build = snake_ids
             .into_iter()
@@ -267,14 +410,88 @@ pub trait SimulableGame<T: SimulatorInstruments, const N_SNAKES: usize>:
     }
     /// simulates the next possible states for a a game with a given set of snakes and moves, producing a list of the new games,
     /// along with the moves that got to that position
-    #[allow(clippy::type_complexity)]
+    ///
+    /// Only the first `N_SNAKES` entries are simulated, and a move listed more than once for a
+    /// snake is only simulated once
     fn simulate_with_moves<S>(
         &self,
         instruments: &T,
         snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
-    ) -> Box<dyn Iterator<Item = (Action<N_SNAKES>, Self)> + '_>
+    ) -> Self::SimulatedStates<'_>
     where
         S: Borrow<[Move]>;
+
+    /// produces the single child state reached when every snake in the action makes its move.
+    /// The default simulates just the chosen move for each snake, games that can skip building
+    /// the iterator should override it
+    fn apply_action(&self, instruments: &T, action: &Action<N_SNAKES>) -> Self {
+        self.simulate_with_moves(instruments, action.iter().map(|(sid, mv)| (sid, [mv])))
+            .next()
+            .map(|(_, child)| child)
+            .expect("simulating a single move for each snake has exactly one child")
+    }
+}
+
+/// Why a snake was eliminated, mirroring the official engine's `EliminatedCause` and
+/// `EliminatedBy`. Causes involving another snake carry the id of that snake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EliminationCause<S> {
+    /// moved off the edge of the board
+    OutOfBounds,
+    /// ran in to its own body (including its neck)
+    SelfCollision,
+    /// ran in to the body of the given snake
+    BodyCollision(S),
+    /// lost (or drew) a head to head against the given snake
+    HeadToHeadCollision(S),
+    /// ran out of health without any help from hazards
+    Starvation,
+    /// ran out of health because of hazard damage
+    HazardDamage,
+    /// eliminated because the given teammate was, when squads share elimination
+    SquadEliminated(S),
+}
+
+/// a game that can report why snakes were eliminated while simulating it
+pub trait EliminationReportingGame<T: SimulatorInstruments, const N_SNAKES: usize>:
+    SimulableGame<T, N_SNAKES>
+{
+    /// the iterator of child states produced by
+    /// [EliminationReportingGame::simulate_with_moves_and_eliminations]
+    #[allow(clippy::type_complexity)]
+    type EliminatingStates<'a>: Iterator<
+            Item = (
+                Action<N_SNAKES>,
+                Self,
+                [Option<EliminationCause<Self::SnakeIDType>>; N_SNAKES],
+            ),
+        > + 'a
+    where
+        Self: 'a;
+
+    /// same as [SimulableGame::simulate_with_moves], but every child state also comes with the
+    /// cause of elimination for each snake eliminated by the moves that produced it, just like
+    /// [EliminationReportingGame::apply_action_with_eliminations]
+    fn simulate_with_moves_and_eliminations<S>(
+        &self,
+        instruments: &T,
+        snake_ids_and_moves: impl IntoIterator<Item = (Self::SnakeIDType, S)>,
+    ) -> Self::EliminatingStates<'_>
+    where
+        S: Borrow<[Move]>;
+
+    /// same as [SimulableGame::apply_action], but also returns the cause of elimination for
+    /// every snake that was eliminated by this action, indexed by snake id. Snakes that survived,
+    /// or were already eliminated before this action, are `None`
+    #[allow(clippy::type_complexity)]
+    fn apply_action_with_eliminations(
+        &self,
+        instruments: &T,
+        action: &Action<N_SNAKES>,
+    ) -> (
+        Self,
+        [Option<EliminationCause<Self::SnakeIDType>>; N_SNAKES],
+    );
 }
 
 /// A game where positions can be checked for hazards
@@ -282,8 +499,14 @@ pub trait HazardQueryableGame: PositionGettableGame {
     /// Is this position a hazard?
     fn is_hazard(&self, pos: &Self::NativePositionType) -> bool;
 
-    /// how much damage do hazards do?
-    fn get_hazard_damage(&self) -> u8;
+    /// how many hazards are stacked on this position? Hazard damage is applied once for every
+    /// hazard in the stack. Games that can't stack hazards report either 0 or 1
+    fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+        self.is_hazard(pos) as u8
+    }
+
+    /// how much damage do hazards do? Negative values heal snakes instead, up to full health
+    fn get_hazard_damage(&self) -> i32;
 }
 
 /// A game where positions can be checked for food
@@ -301,13 +524,34 @@ pub trait NeckQueryableGame: PositionGettableGame + SnakeIDGettableGame {
     fn is_neck(&self, sid: &Self::SnakeIDType, pos: &Self::NativePositionType) -> bool;
 }
 
+/// A game where snakes can play on squads, following the squad ruleset
+pub trait SquadQueryableGame: SnakeIDGettableGame {
+    /// the squad rules in effect, `None` unless this game uses the squad ruleset
+    fn get_squad_settings(&self) -> Option<SquadSettings>;
+
+    /// are these two different snakes on the same squad? Always false outside of squad games
+    fn is_teammate(&self, a: &Self::SnakeIDType, b: &Self::SnakeIDType) -> bool;
+}
+
 /// A game where positions can have their hazards set and cleared
 pub trait HazardSettableGame: PositionGettableGame {
     /// make this position a hazard
     fn set_hazard(&mut self, pos: Self::NativePositionType);
 
+    /// stack another hazard on this position. Games that can't stack hazards just make the
+    /// position a hazard
+    fn add_hazard(&mut self, pos: Self::NativePositionType) {
+        self.set_hazard(pos)
+    }
+
     /// clear this position of being a hazard
     fn clear_hazard(&mut self, pos: Self::NativePositionType);
+
+    /// take one hazard off the stack on this position. Games that can't stack hazards just clear
+    /// the position
+    fn remove_hazard(&mut self, pos: Self::NativePositionType) {
+        self.clear_hazard(pos)
+    }
 }
 
 /// A game for which board positions can be identified and returned
@@ -389,8 +633,61 @@ pub trait RandomReasonableMovesGame: SnakeIDGettableGame {
         &'a self,
         rng: &'a mut impl Rng,
     ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a>;
+
+    /// the moves for a given snake that don't immediately run it into a wall or a snake. The
+    /// default doesn't rule anything out and returns all four moves, so games implemented
+    /// outside this crate only need to override it to give rollout policies better candidates
+    fn reasonable_moves_for_snake(&self, _snake_id: &Self::SnakeIDType) -> Vec<Move> {
+        Move::all().to_vec()
+    }
+
+    /// picks a random move for each snake from its reasonable moves, after they have been
+    /// narrowed down by the given [RolloutPolicy]
+    fn policy_move_for_each_snake<'a, P>(
+        &'a self,
+        policy: &'a P,
+        rng: &'a mut impl Rng,
+    ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a>
+    where
+        Self: Sized,
+        P: RolloutPolicy<Self>,
+    {
+        Box::new(self.get_snake_ids().into_iter().map(move |snake_id| {
+            let mut moves = self.reasonable_moves_for_snake(&snake_id);
+            policy.retain_moves(self, &snake_id, &mut moves);
+            let mv = moves.into_iter().choose(rng).unwrap_or(Move::Up);
+
+            (snake_id, mv)
+        }))
+    }
+}
+
+/// A policy used to narrow down the reasonable moves for a snake during rollouts. See
+/// [crate::rollout_policies] for the built in policies. Policies can be combined by putting them
+/// in a tuple, in which case they are applied in order.
+pub trait RolloutPolicy<G: SnakeIDGettableGame>: std::fmt::Debug {
+    /// removes the moves this policy considers bad for the given snake. Implementations must never
+    /// remove every move, if every candidate is bad the moves should be left untouched
+    fn retain_moves(&self, game: &G, snake_id: &G::SnakeIDType, moves: &mut Vec<Move>);
+}
+
+macro_rules! impl_rollout_policy_for_tuple {
+    ($($name:ident),+) => {
+        impl<G: SnakeIDGettableGame, $($name: RolloutPolicy<G>),+> RolloutPolicy<G> for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn retain_moves(&self, game: &G, snake_id: &G::SnakeIDType, moves: &mut Vec<Move>) {
+                let ($($name,)+) = self;
+                $($name.retain_moves(game, snake_id, moves);)+
+            }
+        }
+    };
 }
 
+impl_rollout_policy_for_tuple!(A);
+impl_rollout_policy_for_tuple!(A, B);
+impl_rollout_policy_for_tuple!(A, B, C);
+impl_rollout_policy_for_tuple!(A, B, C, D);
+
 /// a game for which the neighbors of a given Position can be determined
 pub trait NeighborDeterminableGame: PositionGettableGame {
     /// returns the neighboring positions
@@ -445,9 +742,114 @@ pub trait SnakeBodyGettableGame: PositionGettableGame + SnakeIDGettableGame {
 mod test {
 
     use super::*;
+    use itertools::Itertools;
 
     #[test]
     fn test_move_all_order_matches_iter() {
         assert_eq!(Move::all().to_vec(), Move::all_iter().collect::<Vec<_>>());
     }
+
+    #[derive(Debug)]
+    struct AliveSnakes(Vec<SnakeId>);
+
+    impl SnakeIDGettableGame for AliveSnakes {
+        type SnakeIDType = SnakeId;
+
+        fn get_snake_ids(&self) -> Vec<Self::SnakeIDType> {
+            self.0.clone()
+        }
+    }
+
+    #[derive(Debug)]
+    struct Instruments;
+
+    impl SimulatorInstruments for Instruments {
+        fn observe_simulation(&self, _: Duration) {}
+    }
+
+    /// only implements the required method, like a game defined outside of the crate. Snakes
+    /// that move left are eliminated
+    impl SimulableGame<Instruments, 4> for AliveSnakes {
+        type SimulatedStates<'a> = std::vec::IntoIter<(Action<4>, Self)>;
+
+        fn simulate_with_moves<S>(
+            &self,
+            _instruments: &Instruments,
+            snake_ids_and_moves: impl IntoIterator<Item = (SnakeId, S)>,
+        ) -> Self::SimulatedStates<'_>
+        where
+            S: Borrow<[Move]>,
+        {
+            snake_ids_and_moves
+                .into_iter()
+                .map(|(sid, mvs)| mvs.borrow().iter().map(|mv| (sid, *mv)).collect_vec())
+                .multi_cartesian_product()
+                .map(|joint| {
+                    let alive = joint
+                        .iter()
+                        .filter(|(_, mv)| *mv != Move::Left)
+                        .map(|(sid, _)| *sid);
+                    (
+                        Action::collect_from(joint.iter()),
+                        AliveSnakes(alive.collect()),
+                    )
+                })
+                .collect_vec()
+                .into_iter()
+        }
+    }
+
+    /// only implements the required method, like a game defined outside of the crate
+    impl RandomReasonableMovesGame for AliveSnakes {
+        fn random_reasonable_move_for_each_snake<'a>(
+            &'a self,
+            _rng: &'a mut impl Rng,
+        ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a> {
+            Box::new(self.0.iter().map(|id| (*id, Move::Up)))
+        }
+    }
+
+    #[test]
+    fn test_reasonable_moves_default_to_every_move() {
+        let game = AliveSnakes(vec![SnakeId(0)]);
+        assert_eq!(
+            game.reasonable_moves_for_snake(&SnakeId(0)),
+            Move::all().to_vec()
+        );
+    }
+
+    #[test]
+    fn test_apply_action_defaults_to_simulating() {
+        let game = AliveSnakes(vec![SnakeId(0), SnakeId(1)]);
+        let action = Action::new([Some(Move::Up), Some(Move::Left), None, None]);
+        assert_eq!(game.apply_action(&Instruments, &action).0, vec![SnakeId(0)]);
+    }
+
+    #[test]
+    fn test_standings() {
+        let mut standings = Standings::new();
+        standings.observe(
+            &AliveSnakes(vec![SnakeId(0), SnakeId(1), SnakeId(2), SnakeId(3)]),
+            0,
+        );
+        standings.observe(&AliveSnakes(vec![SnakeId(0), SnakeId(2)]), 5);
+        standings.observe(&AliveSnakes(vec![SnakeId(0)]), 9);
+
+        assert_eq!(standings.turn_of_death(&SnakeId(0)), None);
+        assert_eq!(standings.turn_of_death(&SnakeId(1)), Some(5));
+        assert_eq!(standings.turn_of_death(&SnakeId(2)), Some(9));
+        assert_eq!(standings.placement(&SnakeId(0)), Some(1));
+        assert_eq!(standings.placement(&SnakeId(2)), Some(2));
+        assert_eq!(standings.placement(&SnakeId(1)), Some(3));
+        assert_eq!(standings.placement(&SnakeId(3)), Some(3));
+        assert_eq!(standings.placement(&SnakeId(4)), None);
+        assert_eq!(
+            standings
+                .placements()
+                .into_iter()
+                .map(|(_, placement)| placement)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3, 3]
+        );
+    }
 }
@@ -0,0 +1,727 @@
+//! The ascii grids the `Display` impls print, and a parser for them, which makes it much easier
+//! to read a crash dump or to write down a position in a test than building the json by hand.
+//!
+//! A board is written as one row per line, top row first, with one single character token per
+//! cell separated by whitespace:
+//!
+//! - `.` is an empty cell, `f` is food and `x` is a hazard
+//! - a digit or an uppercase letter is the head of the snake with that label
+//! - `^`, `v`, `<` and `>` are body segments, pointing at the next segment towards the head
+//! - `*` is a tail with stacked segments
+//! - `s` is a body segment of whichever snake it connects to, and a lowercase letter is a body
+//!   segment of the snake with the matching uppercase head. `v` is always a down arrow, so the
+//!   body of a `V` head has to use arrows or `s`
+//!
+//! Bodies are traced outwards from each head, so snakes with touching bodies need to use arrows
+//! or their own letters. Any other line is a header line of the form `key value`, and the
+//! legends the `Display` impls print are made of these:
+//!
+//! ```text
+//! turn 12
+//! ruleset wrapped
+//! hazard_damage 14
+//! you A
+//! A health 90 length 5 id gs_abc
+//! ```
+//!
+//! Snakes default to 100 health and the length of their traced body; a longer `length` stacks
+//! the extra segments on the tail. With the `color` feature every snake is drawn in its own ansi
+//! color, the parser skips the color codes. Printed snakes are labelled with letters from
+//! `A` to `Z`, or digits first on the compact boards, and once those run out every further snake
+//! is drawn as `?`, which can't be parsed back.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::types::{build_snake_id_map, Move, SnakeIDMap, SnakeId};
+
+use super::{BattleSnake, Board, Game, NestedGame, Position, Ruleset, Settings};
+
+#[derive(Debug, Default)]
+struct SnakeHeader {
+    health: Option<i32>,
+    length: Option<usize>,
+    id: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Header {
+    turn: i32,
+    ruleset: Option<String>,
+    hazard_damage: Option<i32>,
+    you: Option<char>,
+    snakes: HashMap<char, SnakeHeader>,
+}
+
+fn is_head(c: char) -> bool {
+    c.is_ascii_digit() || c.is_ascii_uppercase()
+}
+
+fn is_body(c: char) -> bool {
+    (c.is_ascii_lowercase() && c != 'f' && c != 'x') || matches!(c, '^' | '<' | '>' | '*')
+}
+
+fn arrow_move(c: char) -> Option<Move> {
+    match c {
+        '^' => Some(Move::Up),
+        'v' => Some(Move::Down),
+        '<' => Some(Move::Left),
+        '>' => Some(Move::Right),
+        _ => None,
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(key: &str, value: Option<&str>) -> Result<T, Box<dyn Error>> {
+    value
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| format!("header {} needs a numeric value", key).into())
+}
+
+fn single_char(token: &str) -> Option<char> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+fn parse_header_line(header: &mut Header, line: &str) -> Result<(), Box<dyn Error>> {
+    let mut words = line.split_whitespace();
+    let key = words.next().unwrap_or_default();
+    match key {
+        "turn" => header.turn = parse_value(key, words.next())?,
+        "hazard_damage" => header.hazard_damage = Some(parse_value(key, words.next())?),
+        "ruleset" => {
+            header.ruleset = Some(
+                words
+                    .next()
+                    .ok_or("header ruleset needs a name")?
+                    .to_string(),
+            )
+        }
+        "you" => {
+            header.you = Some(
+                words
+                    .next()
+                    .and_then(single_char)
+                    .filter(|c| is_head(*c))
+                    .ok_or("header you needs a snake head token")?,
+            )
+        }
+        _ => {
+            let token = single_char(key)
+                .filter(|c| is_head(*c))
+                .ok_or_else(|| format!("unknown header line: {}", line))?;
+            let snake = header.snakes.entry(token).or_default();
+            while let Some(field) = words.next() {
+                match field {
+                    "health" => snake.health = Some(parse_value(field, words.next())?),
+                    "length" => snake.length = Some(parse_value(field, words.next())?),
+                    "id" => {
+                        snake.id = Some(
+                            words
+                                .next()
+                                .ok_or_else(|| format!("snake {} needs an id", token))?
+                                .to_string(),
+                        )
+                    }
+                    _ => return Err(format!("unknown snake field {} for {}", field, token).into()),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// the snake id a head token is given, `H` heads aren't unique so they are numbered in reading
+/// order
+fn snake_name(token: char, h_count: &mut usize) -> String {
+    if token == 'H' {
+        *h_count += 1;
+        format!("H{}", h_count)
+    } else {
+        token.to_string()
+    }
+}
+
+fn strip_ansi(s: &str) -> String {
+    let mut stripped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| *c == 'm');
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+/// What a single cell of the grid shows, snakes are referred to by their index into the labels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AsciiCell {
+    Empty,
+    Food,
+    Hazard,
+    Head(usize),
+    /// a body segment, with the direction of the next segment towards the head
+    Body(usize, Move),
+    /// a tail with stacked segments
+    Stacked(usize),
+    /// a body segment that isn't next to the one before it, only on broken boards
+    Segment(usize),
+}
+
+/// the label a snake gets when its ids don't fit in a single character, `?` once the 26 letters
+/// run out
+pub(crate) fn snake_label(idx: usize) -> char {
+    (b'A'..=b'Z').nth(idx).map(char::from).unwrap_or('?')
+}
+
+/// the direction from one body segment to the next, across the board edge on wrapped boards
+pub(crate) fn direction(from: Position, to: Position) -> Option<Move> {
+    let unwrap = |d: i32| if d.abs() > 1 { -d.signum() } else { d };
+    match (unwrap(to.x - from.x), unwrap(to.y - from.y)) {
+        (0, 1) => Some(Move::Up),
+        (0, -1) => Some(Move::Down),
+        (-1, 0) => Some(Move::Left),
+        (1, 0) => Some(Move::Right),
+        _ => None,
+    }
+}
+
+fn arrow(mv: Move) -> char {
+    match mv {
+        Move::Up => '^',
+        Move::Down => 'v',
+        Move::Left => '<',
+        Move::Right => '>',
+    }
+}
+
+#[cfg(feature = "color")]
+fn paint(f: &mut fmt::Formatter<'_>, token: char, snake: Option<usize>) -> fmt::Result {
+    const COLORS: [u8; 6] = [32, 34, 35, 33, 36, 31];
+    match snake {
+        Some(idx) => write!(f, "\x1b[{}m{}\x1b[0m", COLORS[idx % COLORS.len()], token),
+        None => write!(f, "{}", token),
+    }
+}
+
+#[cfg(not(feature = "color"))]
+fn paint(f: &mut fmt::Formatter<'_>, token: char, _snake: Option<usize>) -> fmt::Result {
+    write!(f, "{}", token)
+}
+
+/// writes the grid, top row first
+pub(crate) fn write_grid(
+    f: &mut fmt::Formatter<'_>,
+    width: u32,
+    height: u32,
+    labels: &[char],
+    cell: impl Fn(Position) -> AsciiCell,
+) -> fmt::Result {
+    writeln!(f)?;
+    for y in (0..height as i32).rev() {
+        for x in 0..width as i32 {
+            match cell(Position::new(x, y)) {
+                AsciiCell::Empty => paint(f, '.', None)?,
+                AsciiCell::Food => paint(f, 'f', None)?,
+                AsciiCell::Hazard => paint(f, 'x', None)?,
+                AsciiCell::Head(idx) => paint(f, labels[idx], Some(idx))?,
+                AsciiCell::Body(idx, mv) => paint(f, arrow(mv), Some(idx))?,
+                AsciiCell::Stacked(idx) => paint(f, '*', Some(idx))?,
+                AsciiCell::Segment(idx) => paint(f, 's', Some(idx))?,
+            }
+            write!(f, " ")?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+/// writes the legend line of a single snake
+pub(crate) fn write_legend(
+    f: &mut fmt::Formatter<'_>,
+    labels: &[char],
+    idx: usize,
+    health: i64,
+    length: usize,
+    id: Option<&str>,
+) -> fmt::Result {
+    paint(f, labels[idx], Some(idx))?;
+    write!(f, " health {} length {}", health, length)?;
+    if let Some(id) = id {
+        write!(f, " id {}", id)?;
+    }
+    writeln!(f)
+}
+
+/// the cells of the wire representation's snakes, food and hazards. Heads are drawn over bodies,
+/// which are drawn over food and hazards
+pub(crate) fn wire_cells(board: &Board) -> HashMap<Position, AsciiCell> {
+    let mut cells = HashMap::new();
+    for hazard in board.hazards.iter() {
+        cells.insert(*hazard, AsciiCell::Hazard);
+    }
+    for food in board.food.iter() {
+        cells.insert(*food, AsciiCell::Food);
+    }
+    for (idx, snake) in board.snakes.iter().enumerate() {
+        let counts = snake.body.iter().counts();
+        for (toward_head, segment) in snake.body.iter().dedup().tuple_windows() {
+            let cell = if counts[segment] > 1 {
+                AsciiCell::Stacked(idx)
+            } else {
+                match direction(*segment, *toward_head) {
+                    Some(mv) => AsciiCell::Body(idx, mv),
+                    None => AsciiCell::Segment(idx),
+                }
+            };
+            cells.insert(*segment, cell);
+        }
+    }
+    for (idx, snake) in board.snakes.iter().enumerate() {
+        cells.insert(snake.head, AsciiCell::Head(idx));
+    }
+    cells
+}
+
+impl Game {
+    /// Parses a game from an ascii grid, see the `ascii` module for the format
+    pub fn from_ascii(s: &str) -> Result<Game, Box<dyn Error>> {
+        Ok(Self::from_ascii_with_snake_ids(s)?.0)
+    }
+
+    /// Parses a game from an ascii grid along with the snake id map to use when converting it to
+    /// a compact board. When every snake is named by a digit and `you` is `0` the digits are used
+    /// as the snake ids, so the compact board prints the same ids back
+    pub fn from_ascii_with_snake_ids(s: &str) -> Result<(Game, SnakeIDMap), Box<dyn Error>> {
+        let mut header = Header::default();
+        let mut rows: Vec<Vec<char>> = vec![];
+        for line in strip_ansi(s).lines().map(str::trim) {
+            // the packed json line compact boards print for debugging
+            if line.is_empty() || line.starts_with('{') {
+                continue;
+            }
+            let tokens = line
+                .split_whitespace()
+                .map(single_char)
+                .collect::<Option<Vec<_>>>();
+            match tokens {
+                Some(row) => rows.push(row),
+                None => parse_header_line(&mut header, line)?,
+            }
+        }
+
+        let height = rows.len();
+        let width = rows.first().map(Vec::len).ok_or("no board rows found")?;
+        if rows.iter().any(|row| row.len() != width) {
+            return Err("board rows have different widths".into());
+        }
+        let ruleset = header.ruleset.unwrap_or_else(|| "standard".to_string());
+        let wrapped = ruleset == "wrapped";
+
+        let token_at = |p: Position| rows[height - 1 - p.y as usize][p.x as usize];
+        let mut food = vec![];
+        let mut hazards = vec![];
+        let mut heads = vec![];
+        let mut h_count = 0;
+        for (row_idx, row) in rows.iter().enumerate() {
+            for (x, token) in row.iter().enumerate() {
+                let position = Position::new(x as i32, (height - 1 - row_idx) as i32);
+                match *token {
+                    '.' => {}
+                    'f' => food.push(position),
+                    'x' => hazards.push(position),
+                    c if is_head(c) => heads.push((c, snake_name(c, &mut h_count), position)),
+                    c if is_body(c) => {}
+                    c => return Err(format!("unknown board token {}", c).into()),
+                }
+            }
+        }
+        heads.sort_by(|a, b| a.1.cmp(&b.1));
+        if let Some((duplicate, _)) = heads.iter().tuple_windows().find(|(a, b)| a.1 == b.1) {
+            return Err(format!("snake {} appears more than once", duplicate.0).into());
+        }
+
+        let step = |p: Position, mv: Move| {
+            let n = p.add_vec(mv.to_vector());
+            if wrapped {
+                Some(Position::new(
+                    n.x.rem_euclid(width as i32),
+                    n.y.rem_euclid(height as i32),
+                ))
+            } else if n.x < 0 || n.y < 0 || n.x >= width as i32 || n.y >= height as i32 {
+                None
+            } else {
+                Some(n)
+            }
+        };
+
+        let mut claimed = HashSet::new();
+        let mut snakes = vec![];
+        for (token, name, head) in heads.iter() {
+            let own_body = token.to_ascii_lowercase();
+            let snake_header = header.snakes.get(token);
+            let length = snake_header.and_then(|h| h.length);
+            let mut body = VecDeque::from(vec![*head]);
+            loop {
+                let current = *body.back().unwrap();
+                // a known length stops the snake from running into a neighbouring tail
+                let remaining = length.map(|l| l.saturating_sub(body.len()));
+                // arrows pointing back at the current segment are certain, then the snake's own
+                // letter, then the segments that could belong to any snake
+                let candidates = Move::all()
+                    .iter()
+                    .filter_map(|mv| step(current, *mv))
+                    .filter(|p| !claimed.contains(p) && !body.contains(p))
+                    .filter_map(|p| {
+                        let t = token_at(p);
+                        match arrow_move(t) {
+                            Some(mv) if step(p, mv) == Some(current) => Some((0, p)),
+                            Some(_) => None,
+                            None if t == own_body && t != 's' && is_body(t) => Some((1, p)),
+                            None if t == 's' || t == '*' => Some((2, p)),
+                            None => None,
+                        }
+                    })
+                    .filter(|(_, p)| {
+                        let needed = if token_at(*p) == '*' { 2 } else { 1 };
+                        remaining.map_or(true, |r| r >= needed)
+                    })
+                    .collect::<Vec<_>>();
+                let best = candidates.iter().map(|(priority, _)| *priority).min();
+                let candidates = candidates
+                    .into_iter()
+                    .filter(|(priority, _)| Some(*priority) == best)
+                    .map(|(_, p)| p)
+                    .collect::<Vec<_>>();
+                match candidates.as_slice() {
+                    [] => break,
+                    [next] if token_at(*next) == '*' => {
+                        // stacked segments are always the tail
+                        body.push_back(*next);
+                        body.push_back(*next);
+                        break;
+                    }
+                    [next] => body.push_back(*next),
+                    _ => {
+                        return Err(format!(
+                            "the body of snake {} is ambiguous after {:?}, use arrows or its own letter for its body",
+                            token,
+                            current
+                        )
+                        .into())
+                    }
+                }
+            }
+            claimed.extend(body.iter().copied());
+
+            if let Some(length) = length {
+                if length < body.len() {
+                    return Err(format!(
+                        "snake {} has length {} but {} visible segments",
+                        token,
+                        length,
+                        body.len()
+                    )
+                    .into());
+                }
+                let tail = *body.back().unwrap();
+                body.resize(length, tail);
+            }
+            let id = snake_header
+                .and_then(|h| h.id.clone())
+                .unwrap_or_else(|| name.clone());
+            snakes.push(BattleSnake {
+                id: id.clone(),
+                name: id,
+                head: *head,
+                body,
+                health: snake_header.and_then(|h| h.health).unwrap_or(100),
+                shout: None,
+                squad: None,
+                actual_length: None,
+            });
+        }
+
+        for (row_idx, row) in rows.iter().enumerate() {
+            for (x, token) in row.iter().enumerate() {
+                let position = Position::new(x as i32, (height - 1 - row_idx) as i32);
+                if is_body(*token) && !claimed.contains(&position) {
+                    return Err(format!(
+                        "body segment at {:?} isn't connected to a head",
+                        position
+                    )
+                    .into());
+                }
+            }
+        }
+        if let Some(token) = header
+            .snakes
+            .keys()
+            .find(|t| !heads.iter().any(|h| h.0 == **t))
+        {
+            return Err(format!("header mentions snake {} which isn't on the board", token).into());
+        }
+
+        let you_token = header
+            .you
+            .or_else(|| {
+                ['S', '0']
+                    .iter()
+                    .copied()
+                    .find(|t| heads.iter().any(|h| h.0 == *t))
+            })
+            .or_else(|| heads.first().map(|h| h.0))
+            .ok_or("no snakes on the board")?;
+        let you = heads
+            .iter()
+            .position(|h| h.0 == you_token)
+            .map(|idx| snakes[idx].clone())
+            .ok_or_else(|| format!("you ({}) isn't on the board", you_token))?;
+
+        let game = Game {
+            you,
+            board: Board {
+                height: height as u32,
+                width: width as u32,
+                food,
+                snakes,
+                hazards,
+            },
+            turn: header.turn,
+            game: NestedGame {
+                id: "ascii".to_string(),
+                ruleset: Ruleset {
+                    name: ruleset,
+                    version: "v1.0.0".to_string(),
+                    settings: Some(Settings {
+                        food_spawn_chance: 15,
+                        minimum_food: 1,
+                        hazard_damage_per_turn: header.hazard_damage.unwrap_or(14),
+                        hazard_map: None,
+                        hazard_map_author: None,
+                        royale: None,
+                        squad: None,
+                    }),
+                },
+                timeout: 500,
+                map: None,
+                source: None,
+            },
+        };
+
+        let digit_ids = game
+            .board
+            .snakes
+            .iter()
+            .map(|s| s.id.parse::<u8>().ok().filter(|_| s.id.len() == 1))
+            .collect::<Option<Vec<_>>>();
+        let snake_ids = match digit_ids {
+            Some(digits) if game.you.id == "0" => game
+                .board
+                .snakes
+                .iter()
+                .zip(digits)
+                .map(|(s, d)| (s.id.clone(), SnakeId(d)))
+                .collect(),
+            _ => build_snake_id_map(&game),
+        };
+        Ok((game, snake_ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compact_representation::{
+        StandardCellBoard4Snakes11x11, WrappedCellBoard4Snakes11x11,
+    };
+    use crate::types::{
+        HealthGettableGame, LengthGettableGame, PositionGettableGame, SnakeBodyGettableGame,
+    };
+
+    #[test]
+    fn test_parses_header_and_bodies() {
+        let game = Game::from_ascii(
+            "
+            turn 7
+            hazard_damage 20
+            0 health 50 length 4
+            . . f . .
+            . 0 s s .
+            . . . x .
+            1 s s . .
+            . . . . f
+            ",
+        )
+        .unwrap();
+        assert_eq!(game.turn, 7);
+        assert_eq!(game.board.width, 5);
+        assert_eq!(game.board.height, 5);
+        assert_eq!(game.you.id, "0");
+        assert_eq!(game.you.health, 50);
+        assert_eq!(
+            Vec::from(game.you.body.clone()),
+            vec![
+                Position::new(1, 3),
+                Position::new(2, 3),
+                Position::new(3, 3),
+                Position::new(3, 3)
+            ]
+        );
+        let other = &game.board.snakes[1];
+        assert_eq!(other.health, 100);
+        assert_eq!(other.body.len(), 3);
+        assert_eq!(
+            game.board.food,
+            vec![Position::new(2, 4), Position::new(4, 0)]
+        );
+        assert_eq!(game.board.hazards, vec![Position::new(3, 2)]);
+        assert_eq!(
+            game.game
+                .ruleset
+                .settings
+                .as_ref()
+                .unwrap()
+                .hazard_damage_per_turn,
+            20
+        );
+    }
+
+    #[test]
+    fn test_own_letters_disambiguate_bodies() {
+        let ambiguous = "
+            A s s
+            B s s
+            . . .
+            ";
+        assert!(Game::from_ascii(ambiguous).is_err());
+
+        let game = Game::from_ascii(
+            "
+            A a a
+            B b b
+            . . .
+            ",
+        )
+        .unwrap();
+        assert_eq!(game.you.id, "A");
+        assert!(game.board.snakes.iter().all(|s| s.body.len() == 3));
+        assert_eq!(game.board.snakes[1].body[2], Position::new(2, 1));
+    }
+
+    #[test]
+    fn test_wrapped_bodies_cross_edges() {
+        let game = Game::from_ascii(
+            "
+            ruleset wrapped
+            s . . 0
+            . . . .
+            ",
+        )
+        .unwrap();
+        assert!(game.is_wrapped());
+        assert_eq!(
+            Vec::from(game.you.body.clone()),
+            vec![Position::new(3, 1), Position::new(0, 1)]
+        );
+        assert!(Game::from_ascii("s . . 0\n. . . .").is_err());
+    }
+
+    #[test]
+    fn test_round_trips_display() {
+        let game = crate::game_fixture(include_str!("../../fixtures/4_snake_game.json"));
+        let reparsed = Game::from_ascii(&game.to_string()).unwrap();
+        assert_eq!(reparsed.turn, game.turn);
+        assert_eq!(reparsed.game.ruleset.name, game.game.ruleset.name);
+        assert_eq!(reparsed.you.id, game.you.id);
+        assert_eq!(reparsed.board.food, game.board.food);
+        for (parsed, snake) in reparsed.board.snakes.iter().zip(game.board.snakes.iter()) {
+            assert_eq!(parsed.id, snake.id);
+            assert_eq!(parsed.health, snake.health);
+            assert_eq!(parsed.body, snake.body);
+        }
+
+        let board = StandardCellBoard4Snakes11x11::from_ascii(
+            "
+            0 length 5
+            1 health 40 length 4
+            . . . . . . .
+            . 0 < < . f .
+            . . . ^ . . .
+            . . . ^ . x .
+            . 1 < * . . .
+            . 2 . . . . .
+            . . . . . . .
+            ",
+        )
+        .unwrap();
+        let reparsed = StandardCellBoard4Snakes11x11::from_ascii(&board.to_string()).unwrap();
+        assert_eq!(board, reparsed);
+        assert_eq!(reparsed.get_length_i64(&SnakeId(1)), 4);
+        assert_eq!(reparsed.get_health_i64(&SnakeId(1)), 40);
+        assert_eq!(reparsed.get_length_i64(&SnakeId(2)), 1);
+    }
+
+    #[test]
+    fn test_display_draws_arrows_and_stacks() {
+        let ascii = "
+            A length 3
+            . . . . .
+            . A < < .
+            . . . * .
+            . . . B .
+            . . . . .
+            ";
+        let game = Game::from_ascii(ascii).unwrap();
+        let display = strip_ansi(&game.to_string());
+        let grid = display.lines().skip(1).take(5).collect::<Vec<_>>();
+        assert_eq!(
+            grid,
+            vec![
+                ". . . . . ",
+                ". A < < . ",
+                ". . . * . ",
+                ". . . B . ",
+                ". . . . . "
+            ]
+        );
+        assert!(display.contains("A health 100 length 3 id A"));
+        assert!(display.contains("B health 100 length 3 id B"));
+        assert!(display.contains("you A"));
+    }
+
+    #[test]
+    fn test_compact_from_ascii() {
+        let board = StandardCellBoard4Snakes11x11::from_ascii(
+            "
+            2 health 10
+            . . . .
+            . . . .
+            0 s . 2
+            . . . s
+            ",
+        )
+        .unwrap();
+        assert_eq!(board.get_health_i64(&SnakeId(0)), 100);
+        assert_eq!(board.get_health_i64(&SnakeId(1)), 0);
+        assert_eq!(board.get_health_i64(&SnakeId(2)), 10);
+        let body = board
+            .get_snake_body_vec(&SnakeId(2))
+            .into_iter()
+            .map(|p| board.position_from_native(p))
+            .collect::<Vec<_>>();
+        assert_eq!(body, vec![Position::new(3, 1), Position::new(3, 0)]);
+
+        let wrapped = "ruleset wrapped\n0 s .\n. . .\n. . .";
+        assert!(StandardCellBoard4Snakes11x11::from_ascii(wrapped).is_err());
+        assert!(WrappedCellBoard4Snakes11x11::from_ascii(wrapped).is_ok());
+        assert!(StandardCellBoard4Snakes11x11::from_ascii("0 . 7\n. . .\n. . .").is_err());
+    }
+}
@@ -0,0 +1,395 @@
+//! parsers for games exported by the official battlesnake engine, so that recorded games can be
+//! turned in to the wire representation a snake would have been sent on every turn.
+//!
+//! Two formats are supported:
+//! * the engine API, where `/games/{id}` returns an `EngineGameResponse` and
+//!   `/games/{id}/frames` returns `EngineFrames`
+//! * the log written by the CLI's `--output` flag, a JSON lines file with the game on the first
+//!   line, one request per turn, and the result on the last line
+
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::BufRead;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{
+    BattleSnake, Board, Game, NestedGame, Position, RoyaleSettings, Ruleset, Settings,
+    SquadSettings,
+};
+
+/// the body of the engine's `/games/{id}` response
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EngineGameResponse {
+    pub game: EngineGame,
+}
+
+/// the game metadata exported by the engine
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EngineGame {
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub width: u32,
+    pub height: u32,
+    /// the ruleset name under `"name"`, plus every ruleset setting, usually as strings
+    pub ruleset: HashMap<String, Value>,
+    #[serde(default = "default_timeout")]
+    pub snake_timeout: i64,
+    #[serde(default)]
+    pub map: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+fn default_timeout() -> i64 {
+    500
+}
+
+/// the body of the engine's `/games/{id}/frames` response
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EngineFrames {
+    pub count: usize,
+    pub frames: Vec<EngineFrame>,
+}
+
+/// the state of the board on a single turn
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EngineFrame {
+    pub turn: i32,
+    /// every snake in the game, eliminated snakes have `death` set
+    pub snakes: Vec<EngineSnake>,
+    pub food: Vec<EnginePoint>,
+    #[serde(default)]
+    pub hazards: Vec<EnginePoint>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EngineSnake {
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub name: String,
+    pub body: Vec<EnginePoint>,
+    pub health: i32,
+    pub death: Option<EngineDeath>,
+    #[serde(default)]
+    pub shout: Option<String>,
+    #[serde(default)]
+    pub squad: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub struct EngineDeath {
+    pub cause: String,
+    pub turn: i32,
+    #[serde(default)]
+    pub eliminated_by: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnginePoint {
+    #[serde(rename = "X")]
+    pub x: i32,
+    #[serde(rename = "Y")]
+    pub y: i32,
+}
+
+impl From<EnginePoint> for Position {
+    fn from(p: EnginePoint) -> Self {
+        Position::new(p.x, p.y)
+    }
+}
+
+impl EngineGame {
+    /// builds the wire ruleset from the engine's flat map of (mostly stringly typed) settings,
+    /// falling back to the engine's defaults for missing settings
+    pub fn wire_ruleset(&self) -> Ruleset {
+        let int = |key: &str| match self.ruleset.get(key) {
+            Some(Value::Number(n)) => n.as_i64().map(|n| n as i32),
+            Some(Value::String(s)) => s.parse().ok(),
+            _ => None,
+        };
+        let flag = |key: &str| {
+            let value = self
+                .ruleset
+                .get(key)
+                .or_else(|| self.ruleset.get(&format!("squad.{}", key)));
+            match value {
+                Some(Value::Bool(b)) => Some(*b),
+                Some(Value::String(s)) => s.parse().ok(),
+                _ => None,
+            }
+        };
+        let name = match self.ruleset.get("name") {
+            Some(Value::String(name)) => name.clone(),
+            _ => "standard".to_string(),
+        };
+        let squad = if name == "squad" {
+            let defaults = SquadSettings::default();
+            Some(SquadSettings {
+                allow_body_collisions: flag("allowBodyCollisions")
+                    .unwrap_or(defaults.allow_body_collisions),
+                shared_elimination: flag("sharedElimination")
+                    .unwrap_or(defaults.shared_elimination),
+                shared_health: flag("sharedHealth").unwrap_or(defaults.shared_health),
+                shared_length: flag("sharedLength").unwrap_or(defaults.shared_length),
+            })
+        } else {
+            None
+        };
+        let string = |key: &str| match self.ruleset.get(key) {
+            Some(Value::String(s)) if !s.is_empty() => Some(s.clone()),
+            _ => None,
+        };
+        Ruleset {
+            name,
+            version: string("version").unwrap_or_default(),
+            settings: Some(Settings {
+                food_spawn_chance: int("foodSpawnChance").unwrap_or(15),
+                minimum_food: int("minimumFood").unwrap_or(1),
+                hazard_damage_per_turn: int("hazardDamagePerTurn").unwrap_or(14),
+                hazard_map: string("hazardMap"),
+                hazard_map_author: string("hazardMapAuthor"),
+                royale: int("shrinkEveryNTurns").map(|shrink_every_n_turns| RoyaleSettings {
+                    shrink_every_n_turns,
+                }),
+                squad,
+            }),
+        }
+    }
+
+    /// the nested game object every wire request for this game carries
+    pub fn wire_game(&self) -> NestedGame {
+        NestedGame {
+            id: self.id.clone(),
+            ruleset: self.wire_ruleset(),
+            timeout: self.snake_timeout,
+            map: self.map.clone().filter(|m| !m.is_empty()),
+            source: self.source.clone().filter(|s| !s.is_empty()),
+        }
+    }
+}
+
+impl EngineSnake {
+    fn matches(&self, you: &str) -> bool {
+        self.id == you || self.name == you
+    }
+
+    fn to_wire(&self) -> Result<BattleSnake, Box<dyn Error>> {
+        let body: VecDeque<Position> = self.body.iter().map(|p| (*p).into()).collect();
+        let head = *body
+            .front()
+            .ok_or_else(|| format!("snake {} has no body", self.id))?;
+        Ok(BattleSnake {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            head,
+            body,
+            health: self.health,
+            shout: self.shout.clone(),
+            squad: self.squad.clone().filter(|s| !s.is_empty()),
+            actual_length: None,
+        })
+    }
+}
+
+impl EngineFrame {
+    /// converts this frame in to the request `you` (a snake id or name) would have been sent.
+    /// If `you` has been eliminated it isn't on the board, and has 0 health and the body it
+    /// was eliminated with
+    pub fn to_game(&self, game: &EngineGame, you: &str) -> Result<Game, Box<dyn Error>> {
+        let you = self
+            .snakes
+            .iter()
+            .find(|s| s.matches(you))
+            .ok_or_else(|| format!("no snake {} in turn {}", you, self.turn))?;
+        let mut you_snake = you.to_wire()?;
+        if you.death.is_some() {
+            you_snake.health = 0;
+        }
+        let snakes = self
+            .snakes
+            .iter()
+            .filter(|s| s.death.is_none())
+            .map(EngineSnake::to_wire)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Game {
+            you: you_snake,
+            board: Board {
+                height: game.height,
+                width: game.width,
+                food: self.food.iter().map(|p| (*p).into()).collect(),
+                snakes,
+                hazards: self.hazards.iter().map(|p| (*p).into()).collect(),
+            },
+            turn: self.turn,
+            game: game.wire_game(),
+        })
+    }
+}
+
+/// converts engine frames in to the requests `you` (a snake id or name) was sent, up to and
+/// including the frame on which `you` was eliminated
+pub fn games_from_engine_frames(
+    game: &EngineGame,
+    frames: &[EngineFrame],
+    you: &str,
+) -> Result<Vec<Game>, Box<dyn Error>> {
+    let mut games = vec![];
+    for frame in frames {
+        let converted = frame.to_game(game, you)?;
+        let eliminated = converted.you.health == 0;
+        games.push(converted);
+        if eliminated {
+            break;
+        }
+    }
+    Ok(games)
+}
+
+/// parses the JSON lines log written by the CLI's `--output` flag in to the requests `you`
+/// (a snake id or name) was sent. The CLI does not record eliminated snakes, so the frame on
+/// which `you` was eliminated has `you` with 0 health and its body from the previous turn
+pub fn games_from_cli_output<R: BufRead>(
+    reader: R,
+    you: &str,
+) -> Result<Vec<Game>, Box<dyn Error>> {
+    let mut header: Option<NestedGame> = None;
+    let mut games: Vec<Game> = vec![];
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let with_line = |e: serde_json::Error| format!("line {}: {}", line_number + 1, e);
+        let value: Value = serde_json::from_str(&line).map_err(with_line)?;
+        if value.get("board").is_none() {
+            if value.get("ruleset").is_some() {
+                header = Some(serde_json::from_value(value).map_err(with_line)?);
+            }
+            // anything else is the result of the game
+            continue;
+        }
+
+        let board: Board = serde_json::from_value(value["board"].clone()).map_err(with_line)?;
+        let turn: i32 = serde_json::from_value(value["turn"].clone()).map_err(with_line)?;
+        let game = match value.get("game") {
+            Some(game) => serde_json::from_value(game.clone()).map_err(with_line)?,
+            None => header.clone().ok_or_else(|| {
+                format!("line {}: no game before the first turn", line_number + 1)
+            })?,
+        };
+        let you_snake = match board.snakes.iter().find(|s| s.id == you || s.name == you) {
+            Some(snake) => snake.clone(),
+            None => match games.last() {
+                Some(previous) => {
+                    let mut eliminated = previous.you.clone();
+                    eliminated.health = 0;
+                    games.push(Game {
+                        you: eliminated,
+                        board,
+                        turn,
+                        game,
+                    });
+                    break;
+                }
+                None => return Err(format!("no snake {} on the first turn", you).into()),
+            },
+        };
+        games.push(Game {
+            you: you_snake,
+            board,
+            turn,
+            game,
+        });
+    }
+    Ok(games)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path};
+
+    use super::*;
+    use crate::replay::Replay;
+    use crate::types::{build_snake_id_map, Move, VictorDeterminableGame};
+
+    fn fixture_path(name: &str) -> path::PathBuf {
+        path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/engine_export")
+            .join(name)
+    }
+
+    fn engine_fixtures() -> (EngineGame, Vec<EngineFrame>) {
+        let game: EngineGameResponse =
+            serde_json::from_slice(&fs::read(fixture_path("game.json")).unwrap()).unwrap();
+        let frames: EngineFrames =
+            serde_json::from_slice(&fs::read(fixture_path("frames.json")).unwrap()).unwrap();
+        assert_eq!(frames.count, frames.frames.len());
+        (game.game, frames.frames)
+    }
+
+    #[test]
+    fn test_engine_ruleset() {
+        let (game, _) = engine_fixtures();
+        let ruleset = game.wire_ruleset();
+        assert_eq!(ruleset.name, "standard");
+        let settings = ruleset.settings.unwrap();
+        assert_eq!(settings.hazard_damage_per_turn, 14);
+        assert_eq!(settings.royale.unwrap().shrink_every_n_turns, 25);
+        assert_eq!(settings.squad, None);
+    }
+
+    #[test]
+    fn test_games_from_engine_frames() {
+        let (game, frames) = engine_fixtures();
+
+        let alpha = games_from_engine_frames(&game, &frames, "gs_A").unwrap();
+        assert_eq!(alpha.len(), 4);
+        assert!(alpha.iter().all(|g| g.you.id == "gs_A"));
+        assert_eq!(alpha[2].board.snakes.len(), 2);
+        assert_eq!(alpha[3].board.snakes.len(), 1);
+        assert!(alpha[3].you_are_winner());
+
+        // Bravo ran in to the wall on turn 3, found by name rather than id
+        let bravo = games_from_engine_frames(&game, &frames, "Bravo").unwrap();
+        assert_eq!(bravo.len(), 4);
+        assert!(!bravo[2].is_over());
+        assert!(bravo[3].is_over());
+        assert_eq!(bravo[3].you.head, Position::new(7, 3));
+
+        let ids = build_snake_id_map(&alpha[0]);
+        let replay = Replay::<Game, 2>::from_wire_frames(alpha, &ids).unwrap();
+        assert_eq!(replay.actions()[0].own_move(), Move::Up);
+        assert_eq!(replay.actions()[1].iter().count(), 2);
+        // Bravo is gone from the last frame, so its move can't be recovered
+        assert_eq!(replay.actions()[2].iter().count(), 1);
+
+        assert!(games_from_engine_frames(&game, &frames, "Charlie").is_err());
+    }
+
+    #[test]
+    fn test_games_from_cli_output() {
+        let (game, frames) = engine_fixtures();
+        let reader =
+            std::io::BufReader::new(fs::File::open(fixture_path("cli_output.jsonl")).unwrap());
+        let cli = games_from_cli_output(reader, "Bravo").unwrap();
+        let engine = games_from_engine_frames(&game, &frames, "Bravo").unwrap();
+        assert_eq!(cli.len(), engine.len());
+        for (cli, engine) in cli.iter().zip(engine.iter()) {
+            assert_eq!(cli.turn, engine.turn);
+            assert_eq!(cli.board.snakes, engine.board.snakes);
+            assert_eq!(cli.board.food, engine.board.food);
+            assert_eq!(cli.you.health, engine.you.health);
+        }
+        assert!(cli[3].is_over());
+        assert_eq!(cli[3].you.head, Position::new(6, 3));
+    }
+}
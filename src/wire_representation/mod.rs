@@ -6,6 +6,7 @@ use crate::compact_representation::dimensions::Dimensions;
 use crate::compact_representation::CellNum;
 use crate::compact_representation::StandardCellBoard;
 use crate::types::*;
+use itertools::Itertools;
 use rand::prelude::IteratorRandom;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -13,6 +14,9 @@ use std::convert::TryInto;
 use std::error::Error;
 use std::fmt::{self, Display};
 
+pub mod ascii;
+pub mod engine_export;
+
 /// Struct that matches the `battlesnake` object from the wire representation
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct BattleSnake {
@@ -22,6 +26,13 @@ pub struct BattleSnake {
     pub body: VecDeque<Position>,
     pub health: i32,
     pub shout: Option<String>,
+    /// the squad this snake is on, only set in squad games
+    #[serde(
+        default,
+        deserialize_with = "non_empty_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub squad: Option<String>,
     #[serde(skip)]
     pub actual_length: Option<i32>,
 }
@@ -74,34 +85,21 @@ pub struct Board {
 
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f)?;
-        for i in 0..self.height {
-            let k = self.height - i - 1;
-            for j in 0..self.width {
-                let position = Position {
-                    x: j as i32,
-                    y: k as i32,
-                };
-                if self.food.contains(&position) {
-                    write!(f, "f")?;
-                } else if self.snakes.iter().any(|s| s.head == position) {
-                    write!(f, "H")?;
-                } else if self.snakes.iter().any(|s| s.body.contains(&position)) {
-                    write!(f, "s")?;
-                } else if self.hazards.contains(&position) {
-                    write!(f, "x")?;
-                } else {
-                    write!(f, ".")?;
-                }
-                write!(f, " ")?;
-            }
-            writeln!(f)?;
-        }
-        for snake in self.snakes.iter() {
-            write!(
+        let labels = (0..self.snakes.len())
+            .map(ascii::snake_label)
+            .collect::<Vec<_>>();
+        let cells = ascii::wire_cells(self);
+        ascii::write_grid(f, self.width, self.height, &labels, |p| {
+            cells.get(&p).copied().unwrap_or(ascii::AsciiCell::Empty)
+        })?;
+        for (idx, snake) in self.snakes.iter().enumerate() {
+            ascii::write_legend(
                 f,
-                "({} health: {} head: {:?}) ",
-                snake.id, snake.health, snake.head
+                &labels,
+                idx,
+                snake.health as i64,
+                snake.body.len(),
+                Some(&snake.id),
             )?;
         }
         Ok(())
@@ -148,6 +146,8 @@ pub struct Settings {
     )]
     pub hazard_map_author: Option<String>,
     pub royale: Option<RoyaleSettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub squad: Option<SquadSettings>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
@@ -156,6 +156,36 @@ pub struct RoyaleSettings {
     pub shrink_every_n_turns: i32,
 }
 
+/// The settings for the squad ruleset, which only apply to snakes on the same squad
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SquadSettings {
+    /// teammates can move through each others bodies. The compact boards only have room for two
+    /// snakes on a cell, so they refuse to convert squads of three that overlap
+    #[serde(rename = "allowBodyCollisions")]
+    pub allow_body_collisions: bool,
+    /// when one teammate is eliminated the whole squad is eliminated
+    #[serde(rename = "sharedElimination")]
+    pub shared_elimination: bool,
+    /// teammates all share the highest health on the squad
+    #[serde(rename = "sharedHealth")]
+    pub shared_health: bool,
+    /// teammates all share the longest length on the squad
+    #[serde(rename = "sharedLength")]
+    pub shared_length: bool,
+}
+
+impl Default for SquadSettings {
+    /// the engine's defaults for the squad ruleset, which turn every rule on
+    fn default() -> Self {
+        Self {
+            allow_body_collisions: true,
+            shared_elimination: true,
+            shared_health: true,
+            shared_length: true,
+        }
+    }
+}
+
 /// Root object from the battlesnake server in start, move, and end requests, you
 /// probably want to do:
 /// ```
@@ -232,6 +262,17 @@ impl Game {
         self.game.ruleset.name == "wrapped"
     }
 
+    /// Returns a boolean indicating whether this game is using the squad ruleset
+    pub fn is_squad(&self) -> bool {
+        self.game.ruleset.name == "squad"
+    }
+
+    /// Returns a boolean indicating whether this game is using the solo ruleset, where a single
+    /// snake plays until it dies
+    pub fn is_solo(&self) -> bool {
+        self.game.ruleset.name == "solo"
+    }
+
     pub fn is_arcade_maze_map(&self) -> bool {
         self.game.map == Some("arcade_maze".to_owned())
     }
@@ -243,8 +284,33 @@ impl RandomReasonableMovesGame for Game {
         rng: &'a mut impl rand::Rng,
     ) -> Box<dyn Iterator<Item = (Self::SnakeIDType, Move)> + 'a> {
         Box::new(self.board.snakes.iter().map(move |s| {
-            let all_moves = Move::all();
-            let moves = all_moves.iter().filter(|mv| {
+            let moves = self.reasonable_moves_for_snake(&s.id);
+            (
+                s.id.clone(),
+                moves.into_iter().choose(rng).unwrap_or_else(|| {
+                    Move::all()
+                        .iter()
+                        .filter(|mv| {
+                            let new_head = s.head.add_vec(mv.to_vector());
+                            new_head != s.body[1]
+                        })
+                        .choose(rng)
+                        .copied()
+                        .unwrap()
+                }),
+            )
+        }))
+    }
+
+    fn reasonable_moves_for_snake(&self, snake_id: &Self::SnakeIDType) -> Vec<Move> {
+        let s = match self.board.snakes.iter().find(|s| &s.id == snake_id) {
+            Some(s) => s,
+            None => return vec![],
+        };
+
+        Move::all()
+            .iter()
+            .filter(|mv| {
                 let mut new_head = s.head.add_vec(mv.to_vector());
 
                 if self.is_wrapped() {
@@ -257,88 +323,116 @@ impl RandomReasonableMovesGame for Game {
                     };
                 }
 
-                let hazard_damage: i32 = self.get_hazard_damage().into();
+                let hazard_damage =
+                    self.get_hazard_damage() * self.get_hazard_count(&new_head) as i32;
 
                 let unreasonable = self.off_board(new_head)
                     || self.board.snakes.iter().any(|s| s.body.contains(&new_head))
-                    || (self.board.hazards.contains(&new_head) && hazard_damage >= s.health);
+                    || (hazard_damage > 0 && hazard_damage >= s.health);
 
                 !unreasonable
-            });
-            (
-                s.id.clone(),
-                moves.choose(rng).copied().unwrap_or_else(|| {
-                    Move::all()
-                        .iter()
-                        .filter(|mv| {
-                            let new_head = s.head.add_vec(mv.to_vector());
-                            new_head != s.body[1]
-                        })
-                        .choose(rng)
-                        .copied()
-                        .unwrap()
-                }),
-            )
-        }))
+            })
+            .copied()
+            .collect()
     }
 }
 
 impl Display for Game {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f)?;
-        for i in 0..self.board.height {
-            let k = self.board.height - i - 1;
-            for j in 0..self.board.width {
-                let position = Position {
-                    x: j as i32,
-                    y: k as i32,
-                };
-                if self.board.food.contains(&position) {
-                    write!(f, "f")?;
-                } else if self.board.snakes.iter().any(|s| s.head == position) {
-                    if position == self.you.head {
-                        write!(f, "S")?;
-                    } else {
-                        write!(f, "H")?;
-                    }
-                } else if self.board.snakes.iter().any(|s| s.body.contains(&position)) {
-                    write!(f, "s")?;
-                } else if self.board.hazards.contains(&position) {
-                    write!(f, "x")?;
-                } else {
-                    write!(f, ".")?;
-                }
-                write!(f, " ")?;
-            }
-            writeln!(f)?;
-        }
-        for snake in self.board.snakes.iter() {
-            write!(
-                f,
-                "({} health: {} head: {:?}) ",
-                snake.id, snake.health, snake.head
-            )?;
+        write!(f, "{}", self.board)?;
+        writeln!(f, "turn {}", self.turn)?;
+        writeln!(f, "ruleset {}", self.game.ruleset.name)?;
+        if let Some(idx) = self.board.snakes.iter().position(|s| s.id == self.you.id) {
+            writeln!(f, "you {}", ascii::snake_label(idx))?;
         }
         Ok(())
     }
 }
 
+impl SquadQueryableGame for Game {
+    fn get_squad_settings(&self) -> Option<SquadSettings> {
+        if !self.is_squad() {
+            return None;
+        }
+
+        Some(
+            self.game
+                .ruleset
+                .settings
+                .as_ref()
+                .and_then(|settings| settings.squad)
+                .unwrap_or_default(),
+        )
+    }
+
+    fn is_teammate(&self, a: &Self::SnakeIDType, b: &Self::SnakeIDType) -> bool {
+        if !self.is_squad() || a == b {
+            return false;
+        }
+
+        let squad_of = |sid: &String| {
+            self.board
+                .snakes
+                .iter()
+                .find(|s| &s.id == sid)
+                .and_then(|s| s.squad.as_ref())
+        };
+        matches!((squad_of(a), squad_of(b)), (Some(x), Some(y)) if x == y)
+    }
+}
+
+impl Game {
+    /// how many snakes, or squads in squad games, are still alive
+    fn remaining_snakes_or_squads(&self) -> usize {
+        let alive = self.board.snakes.iter().filter(|s| s.health > 0);
+        if self.is_squad() {
+            // snakes without a squad play on their own
+            alive
+                .map(|s| s.squad.as_ref().unwrap_or(&s.id))
+                .unique()
+                .count()
+        } else {
+            alive.count()
+        }
+    }
+}
+
 impl VictorDeterminableGame for Game {
     fn is_over(&self) -> bool {
-        self.you.health == 0 || self.board.snakes.len() == 1
+        let remaining = self.remaining_snakes_or_squads();
+        self.you.health <= 0 || remaining == 0 || (remaining == 1 && !self.is_solo())
     }
 
-    fn get_winner(&self) -> Option<String> {
-        if self.is_over() {
-            Some(
-                self.snake_ids()
-                    .iter()
-                    .find(|s| s != &self.you_id())
-                    .unwrap_or_else(|| self.you_id())
-                    .clone(),
-            )
+    fn get_outcome(&self) -> GameOutcome<Self::SnakeIDType> {
+        let remaining = self.remaining_snakes_or_squads();
+
+        if remaining == 0 && self.is_solo() {
+            GameOutcome::SoloEnded
+        } else if remaining == 0 {
+            // the payload only has the snakes that were eliminated on the final turn, if any
+            let mut eliminated = self
+                .board
+                .snakes
+                .iter()
+                .filter(|s| s.health <= 0)
+                .map(|s| s.id.clone())
+                .collect_vec();
+            if self.you.health <= 0 && !eliminated.contains(&self.you.id) {
+                eliminated.push(self.you.id.clone());
+            }
+            GameOutcome::Draw(eliminated)
+        } else if remaining == 1 && !self.is_solo() {
+            let alive = self.board.snakes.iter().filter(|s| s.health > 0);
+            let winner = alive
+                .clone()
+                .find(|s| s.id == self.you.id)
+                .or_else(|| alive.clone().next())
+                .unwrap();
+            GameOutcome::Win(winner.id.clone())
+        } else if self.you.health <= 0 {
+            GameOutcome::Eliminated
         } else {
-            None
+            GameOutcome::Ongoing
         }
     }
 
@@ -508,13 +602,18 @@ impl HazardQueryableGame for Game {
         self.board.hazards.contains(pos)
     }
 
-    fn get_hazard_damage(&self) -> u8 {
+    fn get_hazard_count(&self, pos: &Self::NativePositionType) -> u8 {
+        let count = self.board.hazards.iter().filter(|h| *h == pos).count();
+        count.min(u8::MAX as usize) as u8
+    }
+
+    fn get_hazard_damage(&self) -> i32 {
         self.game
             .ruleset
             .settings
             .as_ref()
             .map(|settings| settings.hazard_damage_per_turn)
-            .unwrap_or(15) as u8
+            .unwrap_or(15)
     }
 }
 
@@ -523,9 +622,19 @@ impl HazardSettableGame for Game {
         self.board.hazards.push(pos);
     }
 
+    fn add_hazard(&mut self, pos: Self::NativePositionType) {
+        self.board.hazards.push(pos);
+    }
+
     fn clear_hazard(&mut self, pos: Self::NativePositionType) {
         self.board.hazards.retain(|p| p != &pos);
     }
+
+    fn remove_hazard(&mut self, pos: Self::NativePositionType) {
+        if let Some(idx) = self.board.hazards.iter().position(|p| p == &pos) {
+            self.board.hazards.remove(idx);
+        }
+    }
 }
 
 impl NeighborDeterminableGame for Game {
@@ -623,6 +732,72 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_solo_game_over() {
+        let mut g = fixture();
+        let you = g.you.clone();
+        g.board.snakes.retain(|s| s.id == you.id);
+        assert!(g.is_over());
+
+        g.game.ruleset.name = "solo".to_string();
+        assert!(!g.is_over());
+        assert_eq!(g.get_winner(), None);
+
+        g.you.health = 0;
+        g.board.snakes.clear();
+        assert!(g.is_over());
+        assert_eq!(g.get_winner(), None);
+        assert_eq!(g.get_outcome(), GameOutcome::SoloEnded);
+    }
+
+    #[test]
+    fn test_game_outcome() {
+        let mut g = fixture();
+        assert_eq!(g.get_outcome(), GameOutcome::Ongoing);
+
+        // you are out, but the others are still playing
+        let mut eliminated = g.clone();
+        eliminated.you.health = 0;
+        for snake in eliminated.board.snakes.iter_mut() {
+            if snake.id == eliminated.you.id {
+                snake.health = 0;
+            }
+        }
+        assert!(eliminated.is_over());
+        assert_eq!(eliminated.get_outcome(), GameOutcome::Eliminated);
+
+        // you are the last snake standing
+        let you = g.you.clone();
+        g.board.snakes.retain(|s| s.id == you.id);
+        assert_eq!(g.get_outcome(), GameOutcome::Win(you.id.clone()));
+        assert_eq!(g.get_winner(), Some(you.id.clone()));
+
+        g.you.health = 0;
+        g.board.snakes[0].health = 0;
+        assert_eq!(g.get_outcome(), GameOutcome::Draw(vec![you.id]));
+    }
+
+    #[test]
+    fn test_squads() {
+        let mut g = fixture();
+        let ids = g.snake_ids();
+        for (snake, squad) in g.board.snakes.iter_mut().zip(["a", "a", "b", "b"]) {
+            snake.squad = Some(squad.to_string());
+        }
+        assert_eq!(g.get_squad_settings(), None);
+        assert!(!g.is_teammate(&ids[0], &ids[1]));
+
+        g.game.ruleset.name = "squad".to_string();
+        assert_eq!(g.get_squad_settings(), Some(SquadSettings::default()));
+        assert!(g.is_teammate(&ids[0], &ids[1]));
+        assert!(!g.is_teammate(&ids[0], &ids[2]));
+        assert!(!g.is_teammate(&ids[0], &ids[0]));
+        assert!(!g.is_over());
+
+        g.board.snakes.retain(|s| s.squad.as_deref() == Some("b"));
+        assert!(g.is_over());
+    }
+
     #[test]
     fn test_game_you_determinable() {
         let g = fixture();